@@ -0,0 +1,92 @@
+// Encodes the bundled lookup tables in the binary table format when the
+// `embedded-tables` feature is enabled, so they can be included in the executable.
+
+#[allow(dead_code)]
+#[path = "src/binary_table.rs"]
+mod binary_table;
+
+use binary_table::{BinaryHeader, GapRows};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/binary_table.rs");
+    if std::env::var_os("CARGO_FEATURE_EMBEDDED_TABLES").is_none() {
+        return;
+    }
+    for name in ["strategy", "strategyint"] {
+        embed(name);
+    }
+}
+
+/// Converts `<name>.txt` to `<name>.bin` in `OUT_DIR`.
+fn embed(name: &str) {
+    let file_name = format!("{}.txt", name);
+    println!("cargo:rerun-if-changed={}", file_name);
+
+    let text = std::fs::read_to_string(&file_name)
+        .unwrap_or_else(|err| panic!("failed to read {}: {}", file_name, err));
+    let mut header = BinaryHeader::default();
+    let mut gaps: Vec<GapRows> = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        // Carry the header block over; the binary file gets a checksum of its own.
+        if let Some(field) = line.strip_prefix('#') {
+            let (key, value) = field.trim().split_once(' ').unwrap_or((field.trim(), ""));
+            let value = value.trim();
+            let number = || {
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{}:{}: bad header", file_name, line_index + 1))
+            };
+            match key {
+                "slots" => header.max_slots = number(),
+                "values" => header.max_values = number(),
+                "objective" => header.objective = value.to_string(),
+                "generator" => header.generator = value.to_string(),
+                _ => {}
+            }
+            continue;
+        }
+        // Win probabilities come before the rows of their gap shape.
+        if let Some(fields) = line.strip_prefix("value ") {
+            let parts: Vec<&str> = fields.split_whitespace().collect();
+            let bad = || panic!("{}:{}: bad value", file_name, line_index + 1);
+            let [num_slots, num_values, win_probability] = parts[..] else {
+                bad()
+            };
+            gaps.push(GapRows {
+                num_slots: num_slots.parse().unwrap_or_else(|_| bad()),
+                num_values: num_values.parse().unwrap_or_else(|_| bad()),
+                rows: Vec::new(),
+                win_probability: Some(win_probability.parse().unwrap_or_else(|_| bad())),
+            });
+            continue;
+        }
+        let parts: Vec<usize> = line
+            .split_whitespace()
+            .map(|part| {
+                part.parse()
+                    .unwrap_or_else(|_| panic!("{}:{}: bad row", file_name, line_index + 1))
+            })
+            .collect();
+        let [num_slots, num_values, placement_index, upper_bound] = parts[..] else {
+            panic!("{}:{}: expected 4 fields", file_name, line_index + 1);
+        };
+
+        match gaps.last_mut() {
+            Some(gap) if gap.num_slots == num_slots && gap.num_values == num_values => {
+                gap.rows.push((placement_index, upper_bound as i32));
+            }
+            _ => gaps.push(GapRows {
+                num_slots,
+                num_values,
+                rows: vec![(placement_index, upper_bound as i32)],
+                win_probability: None,
+            }),
+        }
+    }
+
+    let bytes = binary_table::encode(&header, &gaps)
+        .unwrap_or_else(|err| panic!("failed to encode {}: {}", file_name, err));
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(format!("{}/{}.bin", out_dir, name), bytes).unwrap();
+}
@@ -0,0 +1,383 @@
+//! The compact binary lookup table format.
+//!
+//! A file starts with a fixed header:
+//!
+//! | bytes | field                                      |
+//! |-------|--------------------------------------------|
+//! | 4     | magic `TNCT`                               |
+//! | 2     | format version                             |
+//! | 2     | board size the table was built for         |
+//! | 4     | number range the table was built for       |
+//! | 4     | number of gap shapes                       |
+//! | 4     | number of rows                             |
+//! | 8     | FNV-1a checksum                            |
+//!
+//! The checksum covers the whole file with the checksum field zeroed. A board size
+//! and number range of 0 mean the game is not recorded. The header is followed by
+//! the objective and the generator, each a 2-byte length and UTF-8 text (empty if
+//! not recorded), then a byte that is 1 if the gap shapes carry win probabilities
+//! and 0 if not.
+//!
+//! Then comes one block per gap shape: the slot count (1 byte), value count
+//! (2 bytes) and row count (1 byte), then each row's placement (1 byte) and upper
+//! bound (2 bytes), then the win probability (an 8-byte float, NaN if unknown) if
+//! the table has them. All numbers are little-endian.
+//!
+//! This module only depends on `std` so the build script can use it too.
+
+pub const MAGIC: &[u8; 4] = b"TNCT";
+pub const VERSION: u16 = 1;
+const HEADER_LEN: usize = 28;
+/// Where the checksum sits in the header.
+const CHECKSUM_RANGE: std::ops::Range<usize> = 20..28;
+/// The size of a gap block with no rows and no win probability.
+const MIN_GAP_LEN: usize = 4;
+
+/// The rows of one gap shape, as `(placement_index, upper_bound)` pairs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapRows {
+    pub num_slots: usize,
+    pub num_values: usize,
+    pub rows: Vec<(usize, i32)>,
+    /// The probability of winning the gap, if the table records it.
+    pub win_probability: Option<f64>,
+}
+
+/// What a binary table records about how it was built.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryHeader {
+    /// The board size the table was built for, or 0 if unknown.
+    pub max_slots: usize,
+    /// The number range the table was built for, or 0 if unknown.
+    pub max_values: usize,
+    pub objective: String,
+    pub generator: String,
+}
+
+/// Returns whether `bytes` starts like a binary table.
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// 64-bit FNV-1a hash.
+pub fn checksum(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Encodes gap shapes in the binary format.
+pub fn encode(header: &BinaryHeader, gaps: &[GapRows]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    for text in [&header.objective, &header.generator] {
+        let len = u16::try_from(text.len()).map_err(|_| format!("'{}' is too long", text))?;
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(text.as_bytes());
+    }
+    let has_values = gaps.iter().any(|gap| gap.win_probability.is_some());
+    body.push(has_values as u8);
+    let mut num_rows = 0usize;
+    for gap in gaps {
+        let num_slots = u8::try_from(gap.num_slots)
+            .map_err(|_| format!("gap with {} slots is too large", gap.num_slots))?;
+        let num_values = u16::try_from(gap.num_values)
+            .map_err(|_| format!("gap with {} values is too large", gap.num_values))?;
+        let row_count = u8::try_from(gap.rows.len()).map_err(|_| {
+            format!(
+                "gap with {} slots and {} values has {} rows",
+                gap.num_slots,
+                gap.num_values,
+                gap.rows.len()
+            )
+        })?;
+        body.push(num_slots);
+        body.extend_from_slice(&num_values.to_le_bytes());
+        body.push(row_count);
+
+        for &(placement_index, upper_bound) in &gap.rows {
+            let placement_index = u8::try_from(placement_index)
+                .map_err(|_| format!("placement {} is too large", placement_index))?;
+            let upper_bound = u16::try_from(upper_bound)
+                .map_err(|_| format!("upper bound {} is out of range", upper_bound))?;
+            body.push(placement_index);
+            body.extend_from_slice(&upper_bound.to_le_bytes());
+        }
+        if has_values {
+            let win_probability = gap.win_probability.unwrap_or(f64::NAN);
+            body.extend_from_slice(&win_probability.to_le_bytes());
+        }
+        num_rows += gap.rows.len();
+    }
+
+    let max_slots = u16::try_from(header.max_slots)
+        .map_err(|_| format!("board size {} is too large", header.max_slots))?;
+    let max_values = u32::try_from(header.max_values)
+        .map_err(|_| format!("number range {} is too large", header.max_values))?;
+    let num_gaps =
+        u32::try_from(gaps.len()).map_err(|_| format!("{} gap shapes are too many", gaps.len()))?;
+    let num_rows =
+        u32::try_from(num_rows).map_err(|_| format!("{} rows are too many", num_rows))?;
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&max_slots.to_le_bytes());
+    bytes.extend_from_slice(&max_values.to_le_bytes());
+    bytes.extend_from_slice(&num_gaps.to_le_bytes());
+    bytes.extend_from_slice(&num_rows.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&body);
+    let checksum = checksum(&bytes);
+    bytes[CHECKSUM_RANGE].copy_from_slice(&checksum.to_le_bytes());
+    Ok(bytes)
+}
+
+/// A little-endian reader over a byte slice that reports truncation as an error.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let end = self.position + len;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| format!("truncated at byte {}", self.position))?;
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn text(&mut self) -> Result<String, String> {
+        let len = self.u16()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| "text is not UTF-8".to_string())
+    }
+}
+
+/// Decodes a binary table, verifying its checksum.
+pub fn decode(bytes: &[u8]) -> Result<(BinaryHeader, Vec<GapRows>), String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err("not a binary lookup table".to_string());
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(format!("unsupported binary table version {}", version));
+    }
+    let max_slots = reader.u16()? as usize;
+    let max_values = reader.u32()? as usize;
+    let num_gaps = reader.u32()? as usize;
+    let num_rows = reader.u32()? as usize;
+    let expected_checksum = reader.u64()?;
+    let mut zeroed = bytes.to_vec();
+    zeroed[CHECKSUM_RANGE].fill(0);
+    if checksum(&zeroed) != expected_checksum {
+        return Err("checksum mismatch".to_string());
+    }
+    let header = BinaryHeader {
+        max_slots,
+        max_values,
+        objective: reader.text()?,
+        generator: reader.text()?,
+    };
+    let has_values = reader.u8()? != 0;
+
+    // A checksum only catches accidents, so never trust the counts past the file's size.
+    let mut gaps = Vec::with_capacity(num_gaps.min((bytes.len() - reader.position) / MIN_GAP_LEN));
+    let mut rows_read = 0;
+    for _ in 0..num_gaps {
+        let num_slots = reader.u8()? as usize;
+        let num_values = reader.u16()? as usize;
+        let row_count = reader.u8()? as usize;
+        let mut rows = Vec::with_capacity(row_count);
+        for _ in 0..row_count {
+            let placement_index = reader.u8()? as usize;
+            let upper_bound = reader.u16()? as i32;
+            rows.push((placement_index, upper_bound));
+        }
+        let win_probability = if has_values {
+            Some(reader.f64()?).filter(|probability| !probability.is_nan())
+        } else {
+            None
+        };
+        rows_read += row_count;
+        gaps.push(GapRows {
+            num_slots,
+            num_values,
+            rows,
+            win_probability,
+        });
+    }
+
+    if rows_read != num_rows {
+        return Err(format!(
+            "header lists {} rows but the file holds {}",
+            num_rows, rows_read
+        ));
+    }
+    if reader.position != bytes.len() {
+        return Err(format!(
+            "{} unexpected bytes after the last gap",
+            bytes.len() - reader.position
+        ));
+    }
+    Ok((header, gaps))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> BinaryHeader {
+        BinaryHeader {
+            max_slots: 20,
+            max_values: 1000,
+            objective: "win".to_string(),
+            generator: "test".to_string(),
+        }
+    }
+
+    fn gaps(win_probability: Option<f64>) -> Vec<GapRows> {
+        vec![
+            GapRows {
+                num_slots: 1,
+                num_values: 3,
+                rows: vec![(0, 2)],
+                win_probability: Some(1.0),
+            },
+            GapRows {
+                num_slots: 2,
+                num_values: 5,
+                rows: vec![(0, 1), (1, 4)],
+                win_probability,
+            },
+        ]
+    }
+
+    /// Writes the checksum of edited bytes into them.
+    fn reseal(bytes: &mut [u8]) {
+        bytes[CHECKSUM_RANGE].fill(0);
+        let checksum = checksum(bytes);
+        bytes[CHECKSUM_RANGE].copy_from_slice(&checksum.to_le_bytes());
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        for gaps in [gaps(Some(0.5)), gaps(None)] {
+            let bytes = encode(&header(), &gaps).unwrap();
+            assert!(is_binary(&bytes));
+            assert_eq!(decode(&bytes).unwrap(), (header(), gaps));
+        }
+        let no_values: Vec<GapRows> = gaps(None)
+            .into_iter()
+            .map(|gap| GapRows {
+                win_probability: None,
+                ..gap
+            })
+            .collect();
+        let bytes = encode(&BinaryHeader::default(), &no_values).unwrap();
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            (BinaryHeader::default(), no_values)
+        );
+    }
+
+    #[test]
+    fn refuses_to_encode_what_does_not_fit() {
+        let mut too_many_slots = gaps(None);
+        too_many_slots[0].num_slots = 256;
+        assert!(encode(&header(), &too_many_slots).is_err());
+        let large_board = BinaryHeader {
+            max_slots: 70_000,
+            ..header()
+        };
+        assert_eq!(
+            encode(&large_board, &gaps(None)).unwrap_err(),
+            "board size 70000 is too large"
+        );
+        let mut negative_bound = gaps(None);
+        negative_bound[0].rows[0].1 = -1;
+        assert!(encode(&header(), &negative_bound).is_err());
+    }
+
+    #[test]
+    fn any_changed_byte_fails_the_checksum() {
+        let bytes = encode(&header(), &gaps(Some(0.5))).unwrap();
+        // Past the magic and version, which are checked before the checksum.
+        for index in 6..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[index] ^= 0x10;
+            assert!(decode(&corrupted).is_err(), "byte {} changed", index);
+        }
+    }
+
+    #[test]
+    fn reports_what_is_wrong_with_a_file() {
+        let bytes = encode(&header(), &gaps(Some(0.5))).unwrap();
+        let error = |bytes: &[u8]| decode(bytes).unwrap_err();
+
+        assert_eq!(error(b"TNC"), "truncated at byte 0");
+        assert_eq!(error(b"ABCD\x04\x00"), "not a binary lookup table");
+        assert_eq!(error(&bytes[..HEADER_LEN - 1]), "truncated at byte 20");
+
+        for unsupported in [0, 2, 4] {
+            let mut version = bytes.clone();
+            version[4] = unsupported;
+            reseal(&mut version);
+            assert_eq!(
+                error(&version),
+                format!("unsupported binary table version {}", unsupported)
+            );
+        }
+
+        let mut body = bytes.clone();
+        *body.last_mut().unwrap() ^= 1;
+        assert_eq!(error(&body), "checksum mismatch");
+
+        let mut truncated = bytes[..bytes.len() - 1].to_vec();
+        reseal(&mut truncated);
+        assert!(error(&truncated).starts_with("truncated at byte"));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        reseal(&mut trailing);
+        assert_eq!(error(&trailing), "1 unexpected bytes after the last gap");
+
+        let mut rows = bytes.clone();
+        rows[16] = 4;
+        reseal(&mut rows);
+        assert_eq!(error(&rows), "header lists 4 rows but the file holds 3");
+    }
+
+    #[test]
+    fn a_huge_gap_count_is_not_trusted() {
+        let mut bytes = encode(&header(), &gaps(None)).unwrap();
+        bytes[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        reseal(&mut bytes);
+        assert!(decode(&bytes).unwrap_err().starts_with("truncated at byte"));
+    }
+}
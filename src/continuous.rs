@@ -0,0 +1,115 @@
+/// The optimal policy in the limit of a continuous number range.
+///
+/// As the number of values grows, a number's position within its gap becomes a
+/// fraction `x` in (0, 1), the other draws of the gap are independent uniform
+/// fractions, and the win probability of a gap only depends on its slot count.
+/// Placing at `x` in slot `p` of a gap with `s` slots then wins with probability
+///
+/// `C(s - 1, p) * x^p * (1 - x)^(s - 1 - p) * win(p) * win(s - 1 - p)`.
+///
+/// Dividing by `(1 - x)^(s - 1)` and taking logs turns each placement into a line
+/// `ln(C(s - 1, p) * win(p) * win(s - 1 - p)) + p * y` in `y = ln(x / (1 - x))`, so the
+/// best placement is the upper envelope of those lines and changes at a handful of
+/// fractional thresholds.
+pub struct ContinuousSolver {
+    /// `win[num_slots]` is the limiting win probability of a gap.
+    win: Vec<f64>,
+    /// `thresholds[num_slots]` lists `(placement, upper)` pairs: a number at fraction
+    /// `x` goes to the placement of the first pair with `x <= upper`.
+    thresholds: Vec<Vec<(usize, f64)>>,
+}
+
+impl ContinuousSolver {
+    /// Solves every gap with up to `max_slots` slots.
+    pub fn new(max_slots: usize) -> Self {
+        let mut solver = Self {
+            win: vec![1.0; max_slots + 1],
+            thresholds: vec![Vec::new(); max_slots + 1],
+        };
+        if max_slots >= 1 {
+            solver.thresholds[1] = vec![(0, 1.0)];
+        }
+
+        for num_slots in 2..=max_slots {
+            let intercept = |placement: usize| {
+                (binom(num_slots - 1, placement)
+                    * solver.win[placement]
+                    * solver.win[num_slots - 1 - placement])
+                    .ln()
+            };
+            let crossing =
+                |low: usize, high: usize| (intercept(low) - intercept(high)) / (high - low) as f64;
+
+            // Lines come in order of increasing slope, so a line leaves the envelope
+            // when the next one overtakes the previous before it could.
+            let mut envelope: Vec<usize> = Vec::new();
+            for placement in 0..num_slots {
+                while envelope.len() >= 2 {
+                    let last = envelope[envelope.len() - 1];
+                    let before = envelope[envelope.len() - 2];
+                    if crossing(before, placement) <= crossing(before, last) {
+                        envelope.pop();
+                    } else {
+                        break;
+                    }
+                }
+                envelope.push(placement);
+            }
+
+            let mut thresholds = Vec::with_capacity(envelope.len());
+            for (i, &placement) in envelope.iter().enumerate() {
+                let upper = match envelope.get(i + 1) {
+                    Some(&next) => 1.0 / (1.0 + (-crossing(placement, next)).exp()),
+                    None => 1.0,
+                };
+                thresholds.push((placement, upper));
+            }
+
+            // Integrate each placement's win probability over its interval.
+            let mut lower = 0.0;
+            let mut win = 0.0;
+            for &(placement, upper) in &thresholds {
+                let mass = binomial_tail(num_slots, upper, placement + 1)
+                    - binomial_tail(num_slots, lower, placement + 1);
+                win += mass / num_slots as f64
+                    * solver.win[placement]
+                    * solver.win[num_slots - 1 - placement];
+                lower = upper;
+            }
+            solver.win[num_slots] = win;
+            solver.thresholds[num_slots] = thresholds;
+        }
+
+        solver
+    }
+
+    /// The limiting probability of winning a gap with `num_slots` slots.
+    pub fn win_probability(&self, num_slots: usize) -> f64 {
+        self.win[num_slots]
+    }
+
+    /// The `(placement, upper)` thresholds of a gap with `num_slots` slots.
+    pub fn thresholds(&self, num_slots: usize) -> &[(usize, f64)] {
+        &self.thresholds[num_slots]
+    }
+
+    /// The best placement for a number at `fraction` of a gap with `num_slots` slots.
+    pub fn placement(&self, num_slots: usize, fraction: f64) -> usize {
+        let thresholds = &self.thresholds[num_slots];
+        let index = thresholds
+            .partition_point(|&(_, upper)| upper < fraction)
+            .min(thresholds.len() - 1);
+        thresholds[index].0
+    }
+}
+
+fn binom(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |product, i| product * (n - k + i) as f64 / i as f64)
+}
+
+/// The probability that at least `k` of `n` independent fractions fall below `x`.
+fn binomial_tail(n: usize, x: f64, k: usize) -> f64 {
+    (k..=n)
+        .map(|j| binom(n, j) * x.powi(j as i32) * (1.0 - x).powi((n - j) as i32))
+        .sum()
+}
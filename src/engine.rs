@@ -1,12 +1,28 @@
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use crate::strategy::Strategy;
-use rand::{rng, Rng};
+use rand::rngs::StdRng;
+use rand::{rng, Rng, RngCore, SeedableRng};
 use rayon::prelude::*;
 
 pub const LOWER_BOUND: i32 = -1; // Lower boundary (no number is less than 0).
 pub const UPPER_BOUND: i32 = 999 + 1; // Upper boundary (all numbers are < UPPER_BOUND).
 pub const NUM_SLOTS: usize = 20; // The board has 20 slots.
+pub const NUM_VALUES: usize = (UPPER_BOUND - LOWER_BOUND - 1) as usize; // Numbers that can be drawn.
+
+/// The shape of a game: the board size and how many different numbers can be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameConfig {
+    pub num_slots: usize,
+    pub num_values: usize,
+}
+
+/// The game the engine plays.
+pub const GAME_CONFIG: GameConfig = GameConfig {
+    num_slots: NUM_SLOTS,
+    num_values: NUM_VALUES,
+};
 
 /// A `Gap` represents a contiguous group of empty slots along with
 /// the boundaries in which a number must lie.
@@ -18,6 +34,20 @@ pub struct Gap {
     pub last_index: usize,
 }
 
+/// A placement made by one strategy during a game.
+#[derive(Debug, Clone)]
+pub struct Decision<'a> {
+    /// Index of the strategy in the slice passed to the engine.
+    pub strategy_index: usize,
+    /// How many numbers the strategy had already placed.
+    pub turn: usize,
+    pub gap: Gap,
+    pub number: i32,
+    pub slot: usize,
+    /// The strategy's board before the placement.
+    pub board: &'a [Option<i32>],
+}
+
 /// The result of a single game simulation.
 #[derive(Debug, Clone)]
 pub struct GameResult {
@@ -99,12 +129,146 @@ pub fn find_valid_gap(board: &[Option<i32>], number: i32) -> Option<Gap> {
     })
 }
 
+/// Chooses the slot for `number` inside `gap`, applying the engine's forced-move
+/// shortcuts unless the strategy asks for full control. Randomized strategies draw
+/// their choices from `rng`.
+pub fn choose_placement(
+    strategy: &dyn Strategy,
+    gap: &Gap,
+    number: i32,
+    board: &[Option<i32>],
+    rng: &mut dyn RngCore,
+) -> usize {
+    if !strategy.want_full_control() {
+        if let Some(slot) = forced_placement(gap, number) {
+            return slot;
+        }
+    }
+    strategy.choose_slot_with_rng(
+        gap.lower,
+        gap.upper,
+        gap.first_index,
+        gap.last_index,
+        number,
+        board,
+        rng,
+    )
+}
+
+/// The slot the engine places `number` in without asking a strategy that does not
+/// want full control: the only slot, an end slot for a number next to the gap's
+/// bound, or the nearer end of a gap with two slots.
+pub fn forced_placement(gap: &Gap, number: i32) -> Option<usize> {
+    if gap.first_index == gap.last_index || number == gap.lower + 1 {
+        Some(gap.first_index)
+    } else if number + 1 == gap.upper {
+        Some(gap.last_index)
+    } else if gap.first_index == gap.last_index - 1 {
+        let dist_lower = number - gap.lower;
+        let dist_upper = gap.upper - number;
+        if dist_lower < dist_upper {
+            Some(gap.first_index)
+        } else {
+            Some(gap.last_index)
+        }
+    } else {
+        None
+    }
+}
+
+/// The base of the generators `new_rng` seeds, from the seed given to `set_seed`.
+static SEED: OnceLock<u64> = OnceLock::new();
+
+/// How many generators `new_rng` has seeded from `SEED`.
+static SEEDED: AtomicU64 = AtomicU64::new(0);
+
+/// Makes every game draw its numbers, and every other user of `new_rng` make its
+/// random choices, from `seed`, so a run can be repeated. Games played in parallel
+/// may get their draws in a different order, but the same games are played.
+pub fn set_seed(seed: u64) {
+    SEED.set(StdRng::seed_from_u64(seed).random())
+        .expect("the seed is only set once");
+}
+
+/// A new random generator: the next of the sequence fixed by the seed given to
+/// `set_seed`, or one seeded from the thread's generator if there is none.
+pub fn new_rng() -> StdRng {
+    match SEED.get() {
+        Some(&base) => StdRng::seed_from_u64(base ^ SEEDED.fetch_add(1, Ordering::Relaxed)),
+        None => StdRng::from_rng(&mut rng()),
+    }
+}
+
+/// Draws `NUM_SLOTS` distinct random numbers between 0 and `UPPER_BOUND`, from the
+/// seed given to `set_seed` if there is one.
+pub fn draw_numbers() -> Vec<i32> {
+    if SEED.get().is_some() {
+        return draw_more_numbers(&[], NUM_SLOTS, &mut new_rng());
+    }
+    draw_more_numbers(&[], NUM_SLOTS, &mut rng())
+}
+
+/// Draws `count` more distinct random numbers between 0 and `UPPER_BOUND`, none of
+/// them among the numbers already `drawn`.
+pub fn draw_more_numbers(drawn: &[i32], count: usize, rng: &mut impl Rng) -> Vec<i32> {
+    // Draw without replacement by rejecting repeats, which are rare with so few draws.
+    let mut numbers = Vec::with_capacity(count);
+    while numbers.len() < count {
+        let number = rng.random_range(0..UPPER_BOUND);
+        if !numbers.contains(&number) && !drawn.contains(&number) {
+            numbers.push(number);
+        }
+    }
+    numbers
+}
+
+/// The placements (0-indexed within the gap) of the number at `offset` that leave
+/// both sides of it with at least as many values as slots; any other placement can
+/// never fill the gap.
+pub fn feasible_placements(
+    num_slots: usize,
+    num_values: usize,
+    offset: usize,
+) -> std::ops::RangeInclusive<usize> {
+    (num_slots + offset).saturating_sub(num_values)..=offset.min(num_slots - 1)
+}
+
+/// The seed of the generator a game's strategies draw their random choices from,
+/// taken from the numbers drawn so that replaying the same draws, or draws from the
+/// same seed, repeats their choices too.
+pub fn game_seed(numbers: &[i32]) -> u64 {
+    // FNV-1a over the numbers.
+    numbers.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &number| {
+        (hash ^ number as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Continues a game on `board`, placing `numbers` in order with `strategy` until one
+/// has no valid gap. Returns how many of them were placed.
+pub fn play_out(
+    strategy: &dyn Strategy,
+    board: &mut [Option<i32>],
+    numbers: &[i32],
+    rng: &mut dyn RngCore,
+) -> usize {
+    for (placed, &number) in numbers.iter().enumerate() {
+        match find_valid_gap(board, number) {
+            Some(gap) => {
+                let slot = choose_placement(strategy, &gap, number, board, rng);
+                board[slot] = Some(number);
+            }
+            None => return placed,
+        }
+    }
+    numbers.len()
+}
+
 /// Simulates a game for multiple strategies using the same shuffled list of numbers.
 ///
 /// # Arguments
 ///
 /// * `strategies` - A slice of tuples, where each tuple contains a name (for identification)
-///                  and a reference to a strategy implementing the `Strategy` trait.
+///   and a reference to a strategy implementing the `Strategy` trait.
 ///
 /// # Returns
 ///
@@ -112,23 +276,47 @@ pub fn find_valid_gap(board: &[Option<i32>], number: i32) -> Option<Gap> {
 pub fn simulate_game_multi(
     strategies: &[(String, Arc<dyn Strategy>)],
 ) -> Vec<(String, GameResult)> {
-    let mut rng = rng();
+    play_game_multi(strategies, &draw_numbers())
+}
 
-    // Generate 20 random numbers been 0 and UPPER_BOUND without replacement
-    let mut numbers = Vec::new();
-    while numbers.len() < NUM_SLOTS {
-        let number = rng.random_range(0..UPPER_BOUND);
-        if !numbers.contains(&number) {
-            numbers.push(number);
-        }
-    }
+/// Plays a game for multiple strategies on a fixed sequence of drawn numbers.
+///
+/// A sequence shorter than `NUM_SLOTS` ends the game once it runs out, so a strategy
+/// that survived every draw is credited with the numbers it placed but not a win.
+///
+/// # Arguments
+///
+/// * `strategies` - A slice of tuples, where each tuple contains the strategy name and reference.
+/// * `numbers` - The numbers in the order they are drawn.
+///
+/// # Returns
+///
+/// A vector of tuples, each containing the strategy name and its corresponding `GameResult`.
+pub fn play_game_multi(
+    strategies: &[(String, Arc<dyn Strategy>)],
+    numbers: &[i32],
+) -> Vec<(String, GameResult)> {
+    play_game_observed(strategies, numbers, |_| {})
+}
 
+/// Plays a game like `play_game_multi`, reporting every placement to `on_decision`.
+pub fn play_game_observed(
+    strategies: &[(String, Arc<dyn Strategy>)],
+    numbers: &[i32],
+    mut on_decision: impl FnMut(Decision<'_>),
+) -> Vec<(String, GameResult)> {
     // Prepare separate boards and result trackers for each strategy.
     let mut boards: Vec<Vec<Option<i32>>> = vec![vec![None; NUM_SLOTS]; strategies.len()];
     let mut placed_counts: Vec<usize> = vec![0; strategies.len()];
     let mut completed: Vec<bool> = vec![false; strategies.len()];
+    // Every strategy gets the same random choices, so they cancel out of comparisons.
+    let seed = game_seed(numbers);
+    let mut rngs: Vec<StdRng> = strategies
+        .iter()
+        .map(|_| StdRng::seed_from_u64(seed))
+        .collect();
 
-    for &number in &numbers {
+    for &number in numbers {
         for (i, (_, strategy)) in strategies.iter().enumerate() {
             // Skip strategies that have already completed.
             if completed[i] {
@@ -136,37 +324,16 @@ pub fn simulate_game_multi(
             }
 
             if let Some(gap) = find_valid_gap(&boards[i], number) {
-                let chosen_slot = if strategy.want_full_control() {
-                    strategy.choose_slot(
-                        gap.lower,
-                        gap.upper,
-                        gap.first_index,
-                        gap.last_index,
-                        number,
-                        &boards[i],
-                    )
-                } else if gap.first_index == gap.last_index || number == gap.lower + 1 {
-                    gap.first_index
-                } else if number + 1 == gap.upper {
-                    gap.last_index
-                } else if gap.first_index == gap.last_index - 1 {
-                    let dist_lower = number - gap.lower;
-                    let dist_upper = gap.upper - number;
-                    if dist_lower < dist_upper {
-                        gap.first_index
-                    } else {
-                        gap.last_index
-                    }
-                } else {
-                    strategy.choose_slot(
-                        gap.lower,
-                        gap.upper,
-                        gap.first_index,
-                        gap.last_index,
-                        number,
-                        &boards[i],
-                    )
-                };
+                let chosen_slot =
+                    choose_placement(strategy.as_ref(), &gap, number, &boards[i], &mut rngs[i]);
+                on_decision(Decision {
+                    strategy_index: i,
+                    turn: placed_counts[i],
+                    gap,
+                    number,
+                    slot: chosen_slot,
+                    board: &boards[i],
+                });
                 boards[i][chosen_slot] = Some(number);
                 placed_counts[i] += 1;
 
@@ -247,3 +414,23 @@ pub fn run_simulations_multi(
         .map(|(i, (name, _))| (name, histograms[i]))
         .collect()
 }
+
+/// Plays every sequence of drawn numbers for multiple strategies.
+///
+/// # Arguments
+///
+/// * `strategies` - A slice of tuples, where each tuple contains the strategy name and reference.
+/// * `sequences` - The draw sequences to play, each in the order the numbers are drawn.
+///
+/// # Returns
+///
+/// One entry per sequence, each holding the results in the same order as `strategies`.
+pub fn run_sequences_multi(
+    strategies: &[(String, Arc<dyn Strategy>)],
+    sequences: &[Vec<i32>],
+) -> Vec<Vec<(String, GameResult)>> {
+    sequences
+        .par_iter()
+        .map(|numbers| play_game_multi(strategies, numbers))
+        .collect()
+}
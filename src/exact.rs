@@ -0,0 +1,295 @@
+use std::fmt;
+
+use bnum::cast::As;
+use bnum::types::U256;
+use rayon::prelude::*;
+
+/// How to choose between placements with exactly the same win probability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// The lowest tied slot.
+    Lowest,
+    /// The highest tied slot. `strategyint.txt` is the exact solution with this rule,
+    /// while `strategy.txt` leaves ties to floating-point rounding.
+    Highest,
+    /// The tied slot closest to the middle of the gap, the lower one if two are as close.
+    Centre,
+}
+
+impl TieBreak {
+    pub const ALL: [TieBreak; 3] = [TieBreak::Lowest, TieBreak::Highest, TieBreak::Centre];
+
+    /// Parses `lowest`, `highest` or `centre`.
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tie_break| tie_break.to_string() == text)
+    }
+
+    /// Picks one of `ties`, the tied placements of a gap with `num_slots` slots in
+    /// increasing order.
+    pub fn choose(self, num_slots: usize, ties: &[usize]) -> usize {
+        match self {
+            TieBreak::Lowest => ties[0],
+            TieBreak::Highest => ties[ties.len() - 1],
+            TieBreak::Centre => *ties
+                .iter()
+                .min_by_key(|&&placement| (2 * placement).abs_diff(num_slots - 1))
+                .unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for TieBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TieBreak::Lowest => write!(f, "lowest"),
+            TieBreak::Highest => write!(f, "highest"),
+            TieBreak::Centre => write!(f, "centre"),
+        }
+    }
+}
+
+/// The optimal win probabilities of `Solver` in exact integer arithmetic.
+///
+/// Instead of probabilities this counts winning draw orders: `count(s, v)` is the
+/// number of ordered ways to draw `s` of a gap's `v` values that the optimal policy
+/// places completely, so the win probability is the count divided by
+/// `v * (v - 1) * ... * (v - s + 1)`. Multiplying the floating-point recurrence
+/// through by those falling factorials leaves
+///
+/// `count(s, v) = sum over offsets of max over placements p of
+///     C(s - 1, p) * count(p, offset) * count(s - 1 - p, v - 1 - offset)`,
+///
+/// which only needs integers (below 10^60 for the full game, so 256 bits).
+/// Placements whose counts are equal are exact ties, and `TieBreak` decides them.
+pub struct ExactSolver {
+    max_values: usize,
+    /// `binomials[n][k]` is "n choose k" for `n < max_slots`.
+    binomials: Vec<Vec<u64>>,
+    /// `counts[num_slots * (max_values + 1) + num_values]` is the number of winning draw orders.
+    counts: Vec<U256>,
+}
+
+impl ExactSolver {
+    /// Solves every gap with up to `max_slots` slots and `max_values` values.
+    pub fn new(max_slots: usize, max_values: usize) -> Self {
+        let mut binomials: Vec<Vec<u64>> = Vec::with_capacity(max_slots);
+        for n in 0..max_slots {
+            let row = (0..=n)
+                .map(|k| {
+                    if k == 0 || k == n {
+                        1
+                    } else {
+                        binomials[n - 1][k - 1] + binomials[n - 1][k]
+                    }
+                })
+                .collect();
+            binomials.push(row);
+        }
+
+        let mut solver = Self {
+            max_values,
+            binomials,
+            counts: vec![U256::ZERO; (max_slots + 1) * (max_values + 1)],
+        };
+
+        // Empty gaps are won by the one empty draw, and a single slot by any of its values.
+        for num_values in 0..=max_values {
+            solver.counts[num_values] = U256::ONE;
+        }
+        if max_slots >= 1 {
+            for num_values in 1..=max_values {
+                solver.counts[(max_values + 1) + num_values] = U256::from(num_values as u64);
+            }
+        }
+
+        // Each gap only depends on gaps with fewer slots, so a whole row can be solved at once.
+        for num_slots in 2..=max_slots {
+            let row: Vec<U256> = (0..=max_values)
+                .into_par_iter()
+                .map(|num_values| {
+                    if num_values < num_slots {
+                        return U256::ZERO;
+                    }
+                    (0..num_values)
+                        .map(|offset| {
+                            (0..num_slots)
+                                .map(|placement| {
+                                    solver.placement_count(num_slots, num_values, offset, placement)
+                                })
+                                .max()
+                                .unwrap()
+                        })
+                        .fold(U256::ZERO, |total, count| total + count)
+                })
+                .collect();
+            let start = num_slots * (max_values + 1);
+            solver.counts[start..start + max_values + 1].copy_from_slice(&row);
+        }
+
+        solver
+    }
+
+    /// The number of ordered draws of `num_slots` values that the optimal policy
+    /// places completely in a gap.
+    pub fn win_count(&self, num_slots: usize, num_values: usize) -> U256 {
+        if num_slots > num_values {
+            return U256::ZERO;
+        }
+        self.counts[num_slots * (self.max_values + 1) + num_values]
+    }
+
+    /// The win probability of a gap, rounded to the nearest `f64`.
+    pub fn win_probability(&self, num_slots: usize, num_values: usize) -> f64 {
+        let orders = (num_values + 1 - num_slots.min(num_values)..=num_values)
+            .fold(U256::ONE, |product, value| {
+                product * U256::from(value as u64)
+            });
+        self.win_count(num_slots, num_values).as_::<f64>() / orders.as_::<f64>()
+    }
+
+    /// The number of winning draw orders after placing the number at `offset` in
+    /// slot `placement` of the gap.
+    pub fn placement_count(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+        placement: usize,
+    ) -> U256 {
+        let right_slots = num_slots - 1 - placement;
+        let right_values = num_values - 1 - offset;
+        if placement > offset || right_slots > right_values {
+            return U256::ZERO;
+        }
+        U256::from(self.binomials[num_slots - 1][placement])
+            * self.win_count(placement, offset)
+            * self.win_count(right_slots, right_values)
+    }
+
+    /// Every placement with the highest win probability, in increasing order.
+    pub fn best_placements(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+    ) -> Vec<usize> {
+        let counts: Vec<U256> = (0..num_slots)
+            .map(|placement| self.placement_count(num_slots, num_values, offset, placement))
+            .collect();
+        let best = *counts.iter().max().unwrap();
+        (0..num_slots)
+            .filter(|&placement| counts[placement] == best)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::Solver;
+
+    const MAX_SLOTS: usize = 8;
+    const MAX_VALUES: usize = 60;
+
+    fn relative_difference(a: f64, b: f64) -> f64 {
+        (a - b).abs() / a.abs().max(b.abs()).max(f64::MIN_POSITIVE)
+    }
+
+    /// The win probability of a board of `values` values with optimal play, found by
+    /// searching every draw and placement of the whole board.
+    fn expectimax(board: &mut [Option<usize>], values: usize) -> f64 {
+        let empty = board.iter().filter(|slot| slot.is_none()).count();
+        if empty == 0 {
+            return 1.0;
+        }
+        let undrawn: Vec<usize> = (0..values)
+            .filter(|value| !board.contains(&Some(*value)))
+            .collect();
+        let total: f64 = undrawn
+            .iter()
+            .map(|&number| {
+                let first = board
+                    .iter()
+                    .rposition(|slot| slot.is_some_and(|value| value < number))
+                    .map_or(0, |index| index + 1);
+                let last = board
+                    .iter()
+                    .position(|slot| slot.is_some_and(|value| value > number))
+                    .unwrap_or(board.len());
+                (first..last)
+                    .map(|slot| {
+                        board[slot] = Some(number);
+                        let probability = expectimax(board, values);
+                        board[slot] = None;
+                        probability
+                    })
+                    .fold(0.0, f64::max)
+            })
+            .sum();
+        total / undrawn.len() as f64
+    }
+
+    #[test]
+    fn the_floating_point_solver_agrees_with_exact_counts() {
+        let exact = ExactSolver::new(MAX_SLOTS, MAX_VALUES);
+        let solver = Solver::new(MAX_SLOTS, MAX_VALUES);
+        for num_slots in 1..=MAX_SLOTS {
+            for num_values in num_slots..=MAX_VALUES {
+                let probability = solver.win_probability(num_slots, num_values);
+                assert!(
+                    relative_difference(probability, exact.win_probability(num_slots, num_values))
+                        < 1e-12,
+                    "{} slots, {} values",
+                    num_slots,
+                    num_values
+                );
+                for offset in 0..num_values {
+                    // Rounding may break an exact tie either way, but never pick a
+                    // placement that is exactly worse by more than rounding.
+                    let chosen = solver.best_placement(num_slots, num_values, offset).0;
+                    let best = exact.best_placements(num_slots, num_values, offset)[0];
+                    let count = |placement| {
+                        exact
+                            .placement_count(num_slots, num_values, offset, placement)
+                            .as_::<f64>()
+                    };
+                    assert!(relative_difference(count(chosen), count(best)) < 1e-12);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exact_counts_match_a_search_of_the_whole_board() {
+        let exact = ExactSolver::new(4, 7);
+        for num_slots in 1..=4 {
+            for num_values in num_slots..=7 {
+                let searched = expectimax(&mut vec![None; num_slots], num_values);
+                let solved = exact.win_probability(num_slots, num_values);
+                assert!(
+                    relative_difference(searched, solved) < 1e-12,
+                    "{} slots, {} values: {} != {}",
+                    num_slots,
+                    num_values,
+                    searched,
+                    solved
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tie_breaks_pick_the_lowest_highest_or_most_central_slot() {
+        let ties = [0, 2, 3, 6];
+        assert_eq!(TieBreak::Lowest.choose(7, &ties), 0);
+        assert_eq!(TieBreak::Highest.choose(7, &ties), 6);
+        assert_eq!(TieBreak::Centre.choose(7, &ties), 3);
+        assert_eq!(TieBreak::Centre.choose(6, &[2, 3]), 2);
+        for tie_break in TieBreak::ALL {
+            assert_eq!(TieBreak::parse(&tie_break.to_string()), Some(tie_break));
+        }
+        assert_eq!(TieBreak::parse("middle"), None);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::engine::{find_valid_gap, NUM_SLOTS};
+use crate::trace::GameTrace;
+
+/// What hindsight says about a recorded game.
+#[derive(Debug, Clone)]
+pub struct Hindsight {
+    /// Index into the trace of the first placement after which the remaining draws
+    /// could no longer all be placed, or `None` if the game stayed winnable.
+    pub fatal_move: Option<usize>,
+    /// The slots that would have kept the game winnable at `fatal_move`.
+    pub saving_slots: Vec<usize>,
+}
+
+/// Returns whether every number in `draws` can still be placed on `board`.
+///
+/// With the draws known in advance the placement is forced: each gap must receive
+/// no more draws than it has slots, and the draws it receives can then be placed in
+/// order of their rank. A number with no valid gap makes the board unwinnable.
+pub fn is_winnable(board: &[Option<i32>], draws: &[i32]) -> bool {
+    let mut gap_counts = [0usize; NUM_SLOTS];
+    for &number in draws {
+        let Some(gap) = find_valid_gap(board, number) else {
+            return false;
+        };
+        gap_counts[gap.first_index] += 1;
+        if gap_counts[gap.first_index] > gap.last_index - gap.first_index + 1 {
+            return false;
+        }
+    }
+    true
+}
+
+/// Finds the first placement in `trace` that turned a winnable game into a lost one,
+/// and the slots that would have saved it.
+pub fn analyse(trace: &GameTrace) -> Hindsight {
+    let numbers = trace.numbers();
+    let mut board = vec![None; NUM_SLOTS];
+
+    for (i, m) in trace.moves.iter().enumerate() {
+        let Some(slot) = m.slot else {
+            break;
+        };
+
+        let before = board.clone();
+        board[slot] = Some(m.number);
+        if is_winnable(&board, &numbers[i + 1..]) {
+            continue;
+        }
+
+        // The board before this move was winnable, so at least one slot saves it.
+        let gap = find_valid_gap(&before, m.number).expect("trace placements are legal");
+        let saving_slots = (gap.first_index..=gap.last_index)
+            .filter(|&candidate| {
+                let mut alternative = before.clone();
+                alternative[candidate] = Some(m.number);
+                is_winnable(&alternative, &numbers[i + 1..])
+            })
+            .collect();
+        return Hindsight {
+            fatal_move: Some(i),
+            saving_slots,
+        };
+    }
+
+    Hindsight {
+        fatal_move: None,
+        saving_slots: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::draw_more_numbers;
+    use crate::strategies::FirstAvailableStrategy;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Whether `draws` can all be placed on `board` by trying every valid slot.
+    fn brute_force_winnable(board: &mut [Option<i32>], draws: &[i32]) -> bool {
+        let Some((&number, rest)) = draws.split_first() else {
+            return true;
+        };
+        let Some(gap) = find_valid_gap(board, number) else {
+            return false;
+        };
+        (gap.first_index..=gap.last_index).any(|slot| {
+            board[slot] = Some(number);
+            let winnable = brute_force_winnable(board, rest);
+            board[slot] = None;
+            winnable
+        })
+    }
+
+    #[test]
+    fn every_draw_fits_an_empty_board() {
+        let draws: Vec<i32> = (0..NUM_SLOTS as i32).map(|i| i * 50).collect();
+        assert!(is_winnable(&[None; NUM_SLOTS], &draws));
+        assert!(is_winnable(&[None; NUM_SLOTS], &[]));
+    }
+
+    #[test]
+    fn a_number_without_a_gap_is_unwinnable() {
+        let mut board = [None; NUM_SLOTS];
+        board[0] = Some(500);
+        assert!(!is_winnable(&board, &[100]));
+        assert!(is_winnable(&board, &[600]));
+    }
+
+    #[test]
+    fn a_gap_cannot_take_more_draws_than_slots() {
+        let mut board = [None; NUM_SLOTS];
+        board[1] = Some(10);
+        assert!(is_winnable(&board, &[3, 50]));
+        assert!(!is_winnable(&board, &[3, 4]));
+    }
+
+    #[test]
+    fn agrees_with_trying_every_placement() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut outcomes = [0; 2];
+        for _ in 0..500 {
+            let numbers = draw_more_numbers(&[], NUM_SLOTS, &mut rng);
+            let (placed, draws) = numbers.split_at(NUM_SLOTS - rng.random_range(1..=5));
+            let mut board = vec![None; NUM_SLOTS];
+            if rng.random_bool(0.5) {
+                // The finished board with the draws taken out, which they can refill.
+                let mut sorted = numbers.clone();
+                sorted.sort();
+                for (slot, number) in sorted.into_iter().enumerate() {
+                    if !draws.contains(&number) {
+                        board[slot] = Some(number);
+                    }
+                }
+            } else {
+                // Empty slots at random positions, keeping the board sorted.
+                let mut placed = placed.to_vec();
+                placed.sort();
+                let mut empty = 0;
+                let mut next = placed.iter();
+                for slot in board.iter_mut() {
+                    if empty < draws.len() && rng.random_range(0..NUM_SLOTS) < draws.len() * 2 {
+                        empty += 1;
+                    } else if let Some(&number) = next.next() {
+                        *slot = Some(number);
+                    }
+                }
+            }
+            let winnable = is_winnable(&board, draws);
+            assert_eq!(
+                winnable,
+                brute_force_winnable(&mut board.clone(), draws),
+                "{:?} {:?}",
+                board,
+                draws
+            );
+            outcomes[winnable as usize] += 1;
+        }
+        assert!(outcomes.iter().all(|&count| count > 50), "{:?}", outcomes);
+    }
+
+    #[test]
+    fn finds_the_move_that_lost_the_game() {
+        // FirstAvailable puts 900 in the first slot, leaving no room below it.
+        let trace = GameTrace::record(&FirstAvailableStrategy, &[900, 100]);
+        let hindsight = analyse(&trace);
+        assert_eq!(hindsight.fatal_move, Some(0));
+        assert!(!hindsight.saving_slots.contains(&0));
+        assert!(hindsight.saving_slots.contains(&(NUM_SLOTS - 1)));
+    }
+}
@@ -1,36 +1,209 @@
+mod binary_table;
+mod continuous;
 mod engine;
+mod exact;
+mod hindsight;
+mod mcts;
+mod objective;
+mod parametric;
+mod regret;
+mod sequences;
+mod solver;
 mod strategies;
 mod strategy;
+mod table;
+mod trace;
+mod training;
+mod tuning;
 
-use engine::run_simulations_multi;
-use std::{io::Write, sync::Arc};
+use continuous::ContinuousSolver;
+use engine::{
+    choose_placement, run_sequences_multi, run_simulations_multi, Gap, GAME_CONFIG, LOWER_BOUND,
+    NUM_SLOTS, NUM_VALUES, UPPER_BOUND,
+};
+use exact::{ExactSolver, TieBreak};
+use objective::{Objective, Policy};
+use parametric::ParametricPolicy;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use regret::{run_regret_multi, RegretBucket, MARGIN_LIMITS};
+use sequences::load_sequences;
+use solver::Solver;
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+};
 use strategies::*;
 use strategy::Strategy;
+use table::{LookupTable, TableMetadata};
+use trace::{load_traces, GameTrace};
+use training::Trainer;
+use tuning::{Parameter, Tunable};
+
+const USAGE: &str = "\
+Usage:
+  twenty_number_challenge [--table <table>] [--fallback <strategy,...>] [--seed <n>] <command>
+      --table replaces strategy.txt as the table of the LookupTable strategy
+      --fallback sets the strategies the lookup table strategies ask, in order, for
+      gaps their table lacks (default BinomialQuantized,OptimalWin; \"none\" to panic)
+      --seed draws the numbers of every game, and the random choices of training,
+      from the given seed, so a run can be repeated
+
+  twenty_number_challenge                     Simulate every registered strategy
+  twenty_number_challenge replay <sequences>  Play every registered strategy on recorded draws
+  twenty_number_challenge trace <strategy> <sequences> <output>
+                                              Record the placements a strategy makes on recorded draws
+  twenty_number_challenge hindsight <traces>  Find the move that lost each recorded game
+  twenty_number_challenge explain <strategy> <slots> <values> <offset>
+                                              Print the score a strategy gives every slot of a gap,
+                                              next to the win probability of each placement
+  twenty_number_challenge regret [games] [strategy...]
+                                              Score every decision against the optimal policy
+  twenty_number_challenge table export <strategy> <output>
+                                              Write a strategy's decisions as a lookup table
+                                              (outputs ending in .bin use the binary format)
+  twenty_number_challenge table diff <a> <b>  List where two lookup tables differ and what it costs
+  twenty_number_challenge table check <table> Print every problem with a lookup table
+  twenty_number_challenge table convert <input> <output>
+                                              Convert a lookup table; outputs ending in .bin are binary
+  twenty_number_challenge table solve <output> [objective]
+                                              Write a lookup table for an objective: the optimal one for
+                                              win (the default, with its win probabilities), or a local
+                                              optimum found by improving the win-optimal table for
+                                              placements (the expected placements) or at-least:<k>
+                                              (placing k numbers)
+  twenty_number_challenge table solve-exact <output> [lowest|highest|centre]
+                                              Write the optimal lookup table solved in exact arithmetic,
+                                              breaking exact ties with the given rule (default lowest)
+  twenty_number_challenge table cache <output> [games]
+                                              Play games with the Solver strategy, which solves gaps
+                                              as it meets them, and save what it solved as a table
+  twenty_number_challenge table ties <table>...
+                                              Count the exact ties each table decides by each rule
+  twenty_number_challenge table annotate <table> <output>
+                                              Add the win probabilities of following a lookup table
+  twenty_number_challenge table explain <table> <slots> <values> <offset>
+                                              Print the win probability of every placement in a gap
+  twenty_number_challenge continuous [values] Print the continuous-limit thresholds and compare
+                                              them with the optimal policy for numbers 0..values
+  twenty_number_challenge train <games> <output> [base]
+                                              Learn a lookup table by playing games, starting from a
+                                              strategy's decisions (default BinomialQuantized), and
+                                              write the exact win rate as it learns to learning_curve.csv
+  twenty_number_challenge train-parametric [iterations]
+                                              Train the weights of the Parametric strategy with
+                                              evolution strategies on the exact win rate
+  twenty_number_challenge tune <strategy> [candidates] [games] [objective] [seed]
+                                              Tune a strategy's parameters by random search and
+                                              successive halving on shared random draws (defaults 16
+                                              candidates, 100000 games in the first round, win, seed 0)
+  twenty_number_challenge bench [games] [strategy...]
+                                              Time the simulation loop for each strategy
+
+The Threshold, Solver, Parametric, EpsilonGreedy, Softmax, Mixture and Ensemble
+strategies, and the BoardAware, Rollout, RolloutGated and Mcts strategies, which are
+too slow to run by default, are only played when bench or regret names them.
+BoardAware maximises the expected placements rather than the win rate. Rollout plays
+the slot whose continuations do best; RolloutGated keeps the base strategy's slot
+unless another wins significantly more often.
+
+Wherever a strategy is named, a strategy that tune accepts can be given its
+parameters after a colon, as in Gaussian:0.1 or CautiousOptimal:87.5 (several
+parameters are separated by slashes), and bench and regret sweep settings separated
+by commas, as in Gaussian:0.02,0.05,0.1,0.2,0.5. The randomized strategies
+(EpsilonGreedy, Softmax, Mixture, Rollout, RolloutGated and Mcts) draw their choices
+from a generator seeded by each game's draws, so replay, trace, tune and runs with
+--seed repeat them exactly.";
+
+/// The table given with `--table`, replacing the default table.
+static TABLE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// The fallback strategies of the lookup table strategies, from `--fallback`.
+static TABLE_FALLBACKS: OnceLock<Vec<String>> = OnceLock::new();
+
+const DEFAULT_FALLBACKS: [&str; 2] = ["BinomialQuantized", "OptimalWin"];
 
 fn main() {
-    let num_simulations = 1_000_000_000;
-    let mut strategies: Vec<(String, Arc<dyn Strategy>)> = vec![
-        ("FirstAvailable".to_string(), Arc::new(FirstAvailableStrategy)),
-        ("LastAvailable".to_string(), Arc::new(LastAvailableStrategy)),
-        ("Middle".to_string(), Arc::new(MiddleStrategy)),
-        ("OptimalWin".to_string(), Arc::new(OptimalWinStrategy)),
-        ("Binomial".to_string(), Arc::new(BinomialStrategy)),
-        ("BinomialQuantized".to_string(), Arc::new(BinomialQuantizedStrategy)),
-        ("LookupTable".to_string(), Arc::new(LookupTableStrategy::new("strategy.txt"))),
-        ("LookupTableInt".to_string(), Arc::new(LookupTableStrategy::new("strategyint.txt"))),
-        // ("Gaussian (σ=0.02)", Arc::new(GaussianStrategy::<20>)),
-        // ("Gaussian (σ=0.05)", Arc::new(GaussianStrategy::<50>)),
-        // ("Gaussian (σ=0.10)", Arc::new(GaussianStrategy::<100>)),
-        // ("Gaussian (σ=0.20)", Arc::new(GaussianStrategy::<200>)),
-        // ("Gaussian (σ=0.30)", Arc::new(GaussianStrategy::<300>)),
-        // ("Gaussian (σ=0.40)", Arc::new(GaussianStrategy::<400>)),
-        // ("Gaussian (σ=0.50)", Arc::new(GaussianStrategy::<500>)),
-        // ("Gaussian (σ=0.60)", Arc::new(GaussianStrategy::<600>)),
-        // ("Gaussian (σ=0.70)", Arc::new(GaussianStrategy::<700>)),
-        // ("Gaussian (σ=0.80)", Arc::new(GaussianStrategy::<800>)),
-        // ("Gaussian (σ=0.90)", Arc::new(GaussianStrategy::<900>)),
-        // ("Gaussian (σ=1.00)", Arc::new(GaussianStrategy::<1000>)),
-        // ("Gaussian (σ=2.00)", Arc::new(GaussianStrategy::<2000>)),
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(file_path) = take_option(&mut args, "--table") {
+        TABLE_OVERRIDE.set(file_path).unwrap();
+    }
+    if let Some(names) = take_option(&mut args, "--fallback") {
+        let names: Vec<String> = match names.as_str() {
+            "none" => Vec::new(),
+            _ => names.split(',').map(str::to_string).collect(),
+        };
+        if let Some(name) = names.iter().find(|name| name.starts_with("LookupTable")) {
+            eprintln!("{} cannot be a fallback of a lookup table strategy", name);
+            std::process::exit(1);
+        }
+        TABLE_FALLBACKS.set(names).unwrap();
+    }
+    if let Some(seed) = take_option(&mut args, "--seed") {
+        let seed = seed.parse().unwrap_or_else(|_| {
+            eprintln!("'{}' is not a seed", seed);
+            std::process::exit(1);
+        });
+        engine::set_seed(seed);
+    }
+
+    match args.first().map(String::as_str) {
+        None => simulate(),
+        Some("replay") if args.len() == 2 => replay(&args[1]),
+        Some("trace") if args.len() == 4 => record_traces(&args[1], &args[2], &args[3]),
+        Some("hindsight") if args.len() == 2 => hindsight(&args[1]),
+        Some("explain") if args.len() == 5 => explain(&args[1..]),
+        Some("regret") => regret(&args[1..]),
+        Some("table") => table_command(&args[1..]),
+        Some("bench") => bench(&args[1..]),
+        Some("continuous") if args.len() <= 2 => continuous(args.get(1).map(String::as_str)),
+        Some("train") if args.len() == 3 || args.len() == 4 => {
+            train(&args[1], &args[2], args.get(3).map(String::as_str))
+        }
+        Some("tune") if (2..=6).contains(&args.len()) => tune(&args[1..]),
+        Some("train-parametric") if args.len() <= 2 => {
+            train_parametric(args.get(1).map(String::as_str))
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// A registered strategy: its name and how to build it.
+type StrategyConstructor = (String, Box<dyn Fn() -> Arc<dyn Strategy>>);
+
+/// Wraps a strategy constructor so strategies are only built when they are used.
+fn lazy<S: Strategy + 'static>(
+    build: impl Fn() -> S + 'static,
+) -> Box<dyn Fn() -> Arc<dyn Strategy>> {
+    Box::new(move || Arc::new(build()))
+}
+
+/// Lists every registered strategy without building any of them.
+fn strategy_constructors() -> Vec<StrategyConstructor> {
+    let mut strategies: Vec<StrategyConstructor> = vec![
+        (
+            "FirstAvailable".to_string(),
+            lazy(|| FirstAvailableStrategy),
+        ),
+        ("LastAvailable".to_string(), lazy(|| LastAvailableStrategy)),
+        ("Middle".to_string(), lazy(|| MiddleStrategy)),
+        ("OptimalWin".to_string(), lazy(|| OptimalWinStrategy)),
+        ("Binomial".to_string(), lazy(|| BinomialStrategy)),
+        (
+            "BinomialQuantized".to_string(),
+            lazy(|| BinomialQuantizedStrategy),
+        ),
+        ("LookupTable".to_string(), lazy(load_default_table_strategy)),
+        (
+            "LookupTableInt".to_string(),
+            lazy(|| load_table_strategy("strategyint.txt")),
+        ),
     ];
 
     // Add CautiousOptimal for 80 to 100 with a step of 5
@@ -38,12 +211,1507 @@ fn main() {
         let strategy_name = format!("CautiousOptimal_{}", i);
         strategies.push((
             strategy_name.clone(),
-            Arc::new(CautiousOptimalStrategy::new(i)),
+            lazy(move || CautiousOptimalStrategy::new(i as f64)),
         ));
     }
 
+    strategies
+}
+
+/// Lists the strategies only built when asked for by name: the ones too slow to run
+/// in every command, and the ones that play the optimal policy or a variant of it.
+fn on_request_constructors() -> Vec<StrategyConstructor> {
+    vec![
+        (
+            "Threshold".to_string(),
+            lazy(|| ThresholdStrategy::new(NUM_SLOTS)),
+        ),
+        (
+            "Solver".to_string(),
+            lazy(|| SolverStrategy::new(NUM_SLOTS)),
+        ),
+        (
+            "Parametric".to_string(),
+            lazy(|| ParametricStrategy::new(ParametricPolicy::TRAINED, NUM_SLOTS)),
+        ),
+        (
+            "EpsilonGreedy".to_string(),
+            lazy(|| EpsilonGreedyStrategy::new(strategy_by_name("Solver"), 0.05)),
+        ),
+        (
+            "Softmax".to_string(),
+            lazy(|| SoftmaxStrategy::new(0.1, NUM_SLOTS)),
+        ),
+        ("Mixture".to_string(), lazy(|| solver_mixture(0.5))),
+        ("Ensemble".to_string(), lazy(|| ensemble([1.0, 1.0, 1.0]))),
+        (
+            "Rollout".to_string(),
+            lazy(|| RolloutStrategy::new(strategy_by_name("BinomialQuantized"), 1000)),
+        ),
+        (
+            "RolloutGated".to_string(),
+            lazy(|| {
+                RolloutStrategy::new(strategy_by_name("BinomialQuantized"), 1000)
+                    .with_significance_gate()
+            }),
+        ),
+        (
+            "BoardAware".to_string(),
+            lazy(|| {
+                // The last iterations of the search barely move the expected placements.
+                let (policy, _) = objective::optimise(
+                    Objective::ExpectedPlacements,
+                    NUM_SLOTS,
+                    NUM_VALUES,
+                    3,
+                    |_, _| {},
+                );
+                BoardAwareStrategy::new(policy)
+            }),
+        ),
+        (
+            "Mcts".to_string(),
+            lazy(|| {
+                MctsStrategy::new(
+                    strategy_by_name("BinomialQuantized"),
+                    Objective::ExpectedPlacements,
+                    2000,
+                    0.5,
+                )
+            }),
+        ),
+    ]
+}
+
+/// Mixes the Solver strategy, with weight `solver_weight`, and BinomialQuantized.
+fn solver_mixture(solver_weight: f64) -> MixtureStrategy {
+    MixtureStrategy::new(vec![
+        (solver_weight, strategy_by_name("Solver")),
+        (1.0 - solver_weight, strategy_by_name("BinomialQuantized")),
+    ])
+}
+
+/// The members of `ensemble`, in the order of their weights.
+const ENSEMBLE_MEMBERS: [&str; 3] = ["BinomialQuantized", "Parametric", "Threshold"];
+
+/// Combines the scores of `ENSEMBLE_MEMBERS` with the given weights.
+fn ensemble(weights: [f64; 3]) -> EnsembleStrategy {
+    EnsembleStrategy::new(
+        weights
+            .into_iter()
+            .zip(ENSEMBLE_MEMBERS)
+            .map(|(weight, name)| (weight, strategy_by_name(name)))
+            .collect(),
+    )
+}
+
+/// Lists the strategies whose numeric parameters `tune` can search.
+fn tunable_strategies() -> Vec<Tunable> {
+    vec![
+        Tunable {
+            name: "CautiousOptimal",
+            parameters: vec![Parameter {
+                name: "epsilon percent",
+                low: 0.0,
+                high: 100.0,
+                integer: false,
+            }],
+            build: Box::new(|values| Arc::new(CautiousOptimalStrategy::new(values[0]))),
+        },
+        Tunable {
+            name: "Gaussian",
+            parameters: vec![Parameter {
+                name: "sigma",
+                low: 0.01,
+                high: 2.0,
+                integer: false,
+            }],
+            build: Box::new(|values| Arc::new(GaussianStrategy::new(values[0]))),
+        },
+        Tunable {
+            name: "EpsilonGreedy",
+            parameters: vec![Parameter {
+                name: "epsilon",
+                low: 0.0,
+                high: 0.2,
+                integer: false,
+            }],
+            build: Box::new(|values| {
+                Arc::new(EpsilonGreedyStrategy::new(
+                    strategy_by_name("Solver"),
+                    values[0],
+                ))
+            }),
+        },
+        Tunable {
+            name: "Softmax",
+            parameters: vec![Parameter {
+                name: "temperature",
+                low: 0.0,
+                high: 1.0,
+                integer: false,
+            }],
+            build: Box::new(|values| Arc::new(SoftmaxStrategy::new(values[0], NUM_SLOTS))),
+        },
+        Tunable {
+            name: "Mixture",
+            parameters: vec![Parameter {
+                name: "Solver weight",
+                low: 0.0,
+                high: 1.0,
+                integer: false,
+            }],
+            build: Box::new(|values| Arc::new(solver_mixture(values[0]))),
+        },
+        Tunable {
+            name: "Ensemble",
+            parameters: ENSEMBLE_MEMBERS
+                .iter()
+                .map(|&name| Parameter {
+                    name,
+                    low: 0.0,
+                    high: 1.0,
+                    integer: false,
+                })
+                .collect(),
+            build: Box::new(|values| Arc::new(ensemble([values[0], values[1], values[2]]))),
+        },
+        Tunable {
+            name: "Parametric",
+            parameters: vec![
+                Parameter {
+                    name: "w0",
+                    low: 0.8,
+                    high: 1.3,
+                    integer: false,
+                },
+                Parameter {
+                    name: "w1",
+                    low: -0.5,
+                    high: 1.0,
+                    integer: false,
+                },
+                Parameter {
+                    name: "w2",
+                    low: -1.0,
+                    high: 0.5,
+                    integer: false,
+                },
+            ],
+            build: Box::new(|values| {
+                Arc::new(ParametricStrategy::new(
+                    [values[0], values[1], values[2]],
+                    NUM_SLOTS,
+                ))
+            }),
+        },
+    ]
+}
+
+/// Builds the list of strategies that every command runs.
+fn registered_strategies() -> Vec<(String, Arc<dyn Strategy>)> {
+    strategy_constructors()
+        .into_iter()
+        .map(|(name, build)| (name, build()))
+        .collect()
+}
+
+/// Removes `name` and the value after it from the arguments, returning the value.
+/// Exits with the usage if the value is missing.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == name)?;
+    if index + 1 >= args.len() {
+        eprintln!("{}", USAGE);
+        std::process::exit(1);
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Some(value)
+}
+
+/// Adds the fallback strategies chosen with `--fallback` to a lookup table strategy.
+fn with_fallbacks(mut strategy: LookupTableStrategy) -> LookupTableStrategy {
+    let names = TABLE_FALLBACKS.get_or_init(|| {
+        DEFAULT_FALLBACKS
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    });
+    for name in names {
+        strategy = strategy.with_fallback(name, strategy_by_name(name));
+    }
+    strategy
+}
+
+/// Loads a bundled lookup table strategy, exiting with an error if the table is unusable.
+fn load_table_strategy(file_name: &str) -> LookupTableStrategy {
+    let strategy = LookupTableStrategy::bundled(file_name).unwrap_or_else(|err| {
+        eprintln!("Failed to load lookup table: {}", err);
+        std::process::exit(1);
+    });
+    with_fallbacks(strategy)
+}
+
+/// Loads the table given with `--table`, or else the bundled `strategy.txt`.
+fn load_default_table_strategy() -> LookupTableStrategy {
+    let result = match TABLE_OVERRIDE.get() {
+        Some(file_path) => LookupTableStrategy::new(file_path),
+        None => LookupTableStrategy::bundled("strategy.txt"),
+    };
+    let strategy = result.unwrap_or_else(|err| {
+        eprintln!("Failed to load lookup table: {}", err);
+        std::process::exit(1);
+    });
+    with_fallbacks(strategy)
+}
+
+/// Prints the counters of every strategy that counted something during a run.
+fn print_stats(strategies: &[(String, Arc<dyn Strategy>)]) {
+    for (name, strategy) in strategies {
+        for (stat, count) in strategy.stats() {
+            if count > 0 {
+                println!("{}: {} {}", name, stat, count);
+            }
+        }
+    }
+}
+
+/// Loads a lookup table, exiting with an error if the table is unusable.
+fn load_table(file_path: &str) -> LookupTable {
+    LookupTable::load(file_path).unwrap_or_else(|err| {
+        eprintln!("Failed to load lookup table: {}", err);
+        std::process::exit(1);
+    })
+}
+
+/// Writes a lookup table, in the binary format if the path ends in `.bin`.
+fn save_table(table: &LookupTable, file_path: &str) {
+    let result = if file_path.ends_with(".bin") {
+        table.save_binary(file_path)
+    } else {
+        table.save(file_path)
+    };
+    result.unwrap_or_else(|err| {
+        eprintln!("Failed to write {}: {}", file_path, err);
+        std::process::exit(1);
+    });
+    println!("Lookup table written to {}", file_path);
+}
+
+/// Builds the strategies a command-line name asks for: a registered strategy, or a
+/// tunable strategy with its parameter values after a colon, as in `Gaussian:0.1`.
+/// Settings separated by commas sweep the parameters, as in `Gaussian:0.05,0.1,0.2`,
+/// and the values of a setting of several parameters are separated by slashes, as
+/// in `Parametric:1.05/0.15/-0.5`. Exits with an error for anything else, including
+/// values outside the parameter's range.
+fn strategies_by_spec(spec: &str) -> Vec<(String, Arc<dyn Strategy>)> {
+    let Some((name, settings)) = spec.split_once(':') else {
+        return vec![(spec.to_string(), strategy_by_name(spec))];
+    };
+    let tunables = tunable_strategies();
+    let Some(tunable) = tunables.iter().find(|tunable| tunable.name == name) else {
+        eprintln!(
+            "Strategy '{}' has no parameters. Strategies with parameters: {}",
+            name,
+            tunables
+                .iter()
+                .map(|tunable| tunable.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+        std::process::exit(1);
+    };
+    settings
+        .split(',')
+        .map(|setting| {
+            let values: Option<Vec<f64>> = setting
+                .split('/')
+                .map(|value| value.trim().parse().ok())
+                .collect();
+            match values {
+                Some(values) if values.len() == tunable.parameters.len() => {
+                    for (parameter, &value) in tunable.parameters.iter().zip(&values) {
+                        if !(parameter.low..=parameter.high).contains(&value) {
+                            eprintln!(
+                                "{} {} of {} is outside {} to {}",
+                                parameter.name, value, name, parameter.low, parameter.high
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                    (format!("{}:{}", name, setting), (tunable.build)(&values))
+                }
+                _ => {
+                    eprintln!(
+                        "Invalid setting '{}' of {}, which takes: {}",
+                        setting,
+                        name,
+                        tunable
+                            .parameters
+                            .iter()
+                            .map(|parameter| parameter.name)
+                            .collect::<Vec<&str>>()
+                            .join("/")
+                    );
+                    std::process::exit(1);
+                }
+            }
+        })
+        .collect()
+}
+
+/// Finds a registered strategy by name, or builds a tunable one from a single
+/// setting such as `Gaussian:0.1`, exiting with an error if there is none.
+fn strategy_by_name(name: &str) -> Arc<dyn Strategy> {
+    if name.contains(':') {
+        let mut strategies = strategies_by_spec(name);
+        if strategies.len() != 1 {
+            eprintln!("'{}' names more than one strategy", name);
+            std::process::exit(1);
+        }
+        return strategies.remove(0).1;
+    }
+    let mut strategies = strategy_constructors();
+    strategies.extend(on_request_constructors());
+    match strategies
+        .iter()
+        .find(|(strategy_name, _)| strategy_name == name)
+    {
+        Some((_, build)) => build(),
+        None => {
+            eprintln!(
+                "Unknown strategy '{}'. Registered strategies: {}",
+                name,
+                strategies
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs random games for every registered strategy.
+fn simulate() {
+    let num_simulations = 1_000_000_000;
+    let strategies = registered_strategies();
     let histograms = run_simulations_multi(&strategies, num_simulations);
+    print_stats(&strategies);
+    write_results(&histograms, num_simulations);
+}
+
+/// Plays every registered strategy on the draw sequences stored in `file_path`.
+fn replay(file_path: &str) {
+    let sequences = load_sequences(file_path).unwrap_or_else(|err| {
+        eprintln!("Failed to load draw sequences: {}", err);
+        std::process::exit(1);
+    });
+    let strategies = registered_strategies();
+    let results = run_sequences_multi(&strategies, &sequences);
+    print_stats(&strategies);
+
+    // Write the placements of every strategy for each sequence.
+    let mut sequence_file = std::fs::File::create("sequence_output.csv").unwrap();
+    writeln!(
+        sequence_file,
+        "Sequence,Draws,{}",
+        strategies
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<&str>>()
+            .join(",")
+    )
+    .unwrap();
+
+    let mut histograms: Vec<(String, [usize; NUM_SLOTS + 1])> = strategies
+        .iter()
+        .map(|(name, _)| (name.clone(), [0; NUM_SLOTS + 1]))
+        .collect();
+    for (sequence_index, (numbers, sequence_results)) in sequences.iter().zip(&results).enumerate()
+    {
+        for (i, (_name, result)) in sequence_results.iter().enumerate() {
+            histograms[i].1[result.placed_count] += 1;
+        }
+
+        writeln!(
+            sequence_file,
+            "{},{},{}",
+            sequence_index + 1,
+            numbers
+                .iter()
+                .map(|number| number.to_string())
+                .collect::<Vec<String>>()
+                .join(" "),
+            sequence_results
+                .iter()
+                .map(|(_name, result)| result.placed_count.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+        .unwrap();
+    }
+    println!("Per-sequence output written to sequence_output.csv");
+
+    write_results(&histograms, sequences.len());
+}
+
+/// Plays `strategy_name` on the draw sequences in `sequences_path` and writes the traces.
+fn record_traces(strategy_name: &str, sequences_path: &str, output_path: &str) {
+    let strategy = strategy_by_name(strategy_name);
+    let sequences = load_sequences(sequences_path).unwrap_or_else(|err| {
+        eprintln!("Failed to load draw sequences: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut file = std::fs::File::create(output_path).unwrap();
+    writeln!(file, "# {} on {}", strategy_name, sequences_path).unwrap();
+    for numbers in &sequences {
+        writeln!(file, "{}", GameTrace::record(strategy.as_ref(), numbers)).unwrap();
+    }
+    println!("Traces written to {}", output_path);
+}
+
+/// Reports, for every lost game in `traces_path`, the first placement that made it unwinnable.
+fn hindsight(traces_path: &str) {
+    let traces = load_traces(traces_path).unwrap_or_else(|err| {
+        eprintln!("Failed to load traces: {}", err);
+        std::process::exit(1);
+    });
+
+    let mut file = std::fs::File::create("hindsight_output.csv").unwrap();
+    writeln!(
+        file,
+        "Game,Placed,Won,Fatal move,Number,Chosen slot,Saving slots"
+    )
+    .unwrap();
+
+    let mut lost_games = 0;
+    let mut fatal_histogram = [0usize; NUM_SLOTS];
+    for (game_index, trace) in traces.iter().enumerate() {
+        let placed = trace.placed_count();
+        let won = placed == NUM_SLOTS;
+        if trace.lost() {
+            lost_games += 1;
+        }
+
+        let analysis = hindsight::analyse(trace);
+        match analysis.fatal_move {
+            Some(move_index) => {
+                fatal_histogram[move_index] += 1;
+                let m = trace.moves[move_index];
+                writeln!(
+                    file,
+                    "{},{},{},{},{},{},{}",
+                    game_index + 1,
+                    placed,
+                    won,
+                    move_index + 1,
+                    m.number,
+                    m.slot.unwrap(),
+                    analysis
+                        .saving_slots
+                        .iter()
+                        .map(|slot| slot.to_string())
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                )
+                .unwrap();
+            }
+            None => {
+                writeln!(file, "{},{},{},,,,", game_index + 1, placed, won).unwrap();
+            }
+        }
+    }
+
+    println!(
+        "{} games, {} lost. Move that made each lost game unwinnable:",
+        traces.len(),
+        lost_games
+    );
+    for (move_index, &count) in fatal_histogram.iter().enumerate() {
+        if count > 0 {
+            println!("  move {:>2}: {}", move_index + 1, count);
+        }
+    }
+    println!("Output written to hindsight_output.csv");
+}
+
+/// Parses a count argument, exiting with an error if it is not a number.
+fn parse_count(arg: Option<&str>, default: usize) -> usize {
+    match arg {
+        Some(arg) => arg.replace('_', "").parse().unwrap_or_else(|_| {
+            eprintln!("'{}' is not a count", arg);
+            std::process::exit(1);
+        }),
+        None => default,
+    }
+}
+
+/// Parses a count like `parse_count`, exiting with an error if it is 0.
+fn parse_positive_count(arg: Option<&str>, default: usize) -> usize {
+    let count = parse_count(arg, default);
+    if count == 0 {
+        eprintln!("'{}' must be at least 1", arg.unwrap_or_default());
+        std::process::exit(1);
+    }
+    count
+}
+
+/// Times the simulation loop for each strategy on its own.
+fn bench(args: &[String]) {
+    let num_simulations = parse_count(args.first().map(String::as_str), 10_000_000);
+    let names: Vec<&str> = if args.len() > 1 {
+        args[1..].iter().map(String::as_str).collect()
+    } else {
+        vec!["LookupTable", "BinomialQuantized", "OptimalWin"]
+    };
+
+    for (name, strategy) in names.into_iter().flat_map(strategies_by_spec) {
+        let strategies = vec![(name.clone(), strategy)];
+        let start = std::time::Instant::now();
+        let histograms = run_simulations_multi(&strategies, num_simulations);
+        let elapsed = start.elapsed();
+        let placements: usize = histograms[0]
+            .1
+            .iter()
+            .enumerate()
+            .map(|(placed, &games)| placed * games)
+            .sum();
+        println!(
+            "{}: {:.1} ns/game ({:.3}s, win rate {:.4}%, average placements {:.4})",
+            name,
+            elapsed.as_nanos() as f64 / num_simulations as f64,
+            elapsed.as_secs_f64(),
+            histograms[0].1[NUM_SLOTS] as f64 / num_simulations as f64 * 100.0,
+            placements as f64 / num_simulations as f64
+        );
+        print_stats(&strategies);
+    }
+}
+
+/// Scores every decision of every registered strategy against the optimal policy.
+fn regret(args: &[String]) {
+    let num_simulations = parse_count(args.first().map(String::as_str), 1_000_000);
+    let strategies = if args.len() > 1 {
+        args[1..]
+            .iter()
+            .flat_map(|name| strategies_by_spec(name))
+            .collect()
+    } else {
+        registered_strategies()
+    };
+
+    let solver = Solver::new(NUM_SLOTS, NUM_VALUES);
+    println!(
+        "Optimal win rate: {:.6}%",
+        solver.win_probability(NUM_SLOTS, NUM_VALUES) * 100.0
+    );
+
+    let results = run_regret_multi(&strategies, &solver, num_simulations);
+    print_stats(&strategies);
+
+    let mut gap_file = std::fs::File::create("regret_by_gap.csv").unwrap();
+    let mut turn_file = std::fs::File::create("regret_by_turn.csv").unwrap();
+    let mut margin_file = std::fs::File::create("regret_by_margin.csv").unwrap();
+    writeln!(
+        gap_file,
+        "Strategy,Gap slots,Decisions,Suboptimal decisions,Total regret,Mean regret"
+    )
+    .unwrap();
+    writeln!(
+        turn_file,
+        "Strategy,Turn,Decisions,Suboptimal decisions,Total regret,Mean regret"
+    )
+    .unwrap();
+    writeln!(
+        margin_file,
+        "Strategy,Margin below,Decisions,Suboptimal decisions,Total regret,Mean regret"
+    )
+    .unwrap();
+
+    for (strategy_name, stats) in &results {
+        let total = stats.total();
+        println!(
+            "{}: {} of {} decisions suboptimal, regret per game {:.6}",
+            strategy_name,
+            total.suboptimal,
+            total.decisions,
+            total.total_regret / num_simulations as f64
+        );
+        // Decisions the strategy's own scores called within 1%.
+        let near_ties =
+            stats.by_margin[..2]
+                .iter()
+                .fold(RegretBucket::default(), |mut near_ties, bucket| {
+                    near_ties.merge(bucket);
+                    near_ties
+                });
+        if near_ties.decisions > 0 {
+            println!(
+                "  {} decisions within 1% by its own scores, {} of them suboptimal",
+                near_ties.decisions, near_ties.suboptimal
+            );
+        }
+
+        for (num_slots, bucket) in stats.by_gap_slots.iter().enumerate().skip(2) {
+            writeln!(
+                gap_file,
+                "{},{},{},{},{},{}",
+                strategy_name,
+                num_slots,
+                bucket.decisions,
+                bucket.suboptimal,
+                bucket.total_regret,
+                bucket.mean_regret()
+            )
+            .unwrap();
+        }
+        for (bucket, limit) in stats.by_margin.iter().zip(
+            MARGIN_LIMITS
+                .iter()
+                .map(ToString::to_string)
+                .chain(["inf".to_string()]),
+        ) {
+            writeln!(
+                margin_file,
+                "{},{},{},{},{},{}",
+                strategy_name,
+                limit,
+                bucket.decisions,
+                bucket.suboptimal,
+                bucket.total_regret,
+                bucket.mean_regret()
+            )
+            .unwrap();
+        }
+        for (turn, bucket) in stats.by_turn.iter().enumerate() {
+            writeln!(
+                turn_file,
+                "{},{},{},{},{},{}",
+                strategy_name,
+                turn + 1,
+                bucket.decisions,
+                bucket.suboptimal,
+                bucket.total_regret,
+                bucket.mean_regret()
+            )
+            .unwrap();
+        }
+    }
+    println!("Output written to regret_by_gap.csv, regret_by_turn.csv and regret_by_margin.csv");
+}
+
+/// Learns a lookup table from games played in the engine, evaluating it exactly at
+/// doubling numbers of games.
+fn train(num_games: &str, output_path: &str, base_name: Option<&str>) {
+    let num_games = parse_count(Some(num_games), 0);
+    let base_name = base_name.unwrap_or("BinomialQuantized");
+    let base_table =
+        LookupTable::from_strategy(strategy_by_name(base_name).as_ref(), NUM_SLOTS, NUM_VALUES);
+    let base = Policy::from_fn(NUM_SLOTS, NUM_VALUES, |num_slots, num_values, offset| {
+        base_table
+            .placement(num_slots, num_values, offset as i32)
+            .unwrap()
+    });
+    let mut trainer = Trainer::new(base, 0.1);
+
+    let mut curve_file = std::fs::File::create("learning_curve.csv").unwrap();
+    writeln!(curve_file, "Games,States,Win rate").unwrap();
+    let mut checkpoint = 10_000.min(num_games);
+    for game in 1..=num_games {
+        trainer.play_game();
+        if game == checkpoint || game == num_games {
+            let solver =
+                Solver::evaluate(NUM_SLOTS, NUM_VALUES, |num_slots, num_values, offset| {
+                    trainer.greedy(num_slots, num_values, offset)
+                });
+            let win_rate = solver.win_probability(NUM_SLOTS, NUM_VALUES);
+            println!(
+                "{} games: {} states tried, win rate {:.6}%",
+                game,
+                trainer.num_states(),
+                win_rate * 100.0
+            );
+            writeln!(curve_file, "{},{},{}", game, trainer.num_states(), win_rate).unwrap();
+            checkpoint *= 2;
+        }
+    }
+    println!(
+        "Optimal win rate: {:.6}%",
+        Solver::new(NUM_SLOTS, NUM_VALUES).win_probability(NUM_SLOTS, NUM_VALUES) * 100.0
+    );
+
+    let mut table = LookupTable::from_policy(NUM_SLOTS, NUM_VALUES, |key, offset| {
+        trainer.greedy(key.num_slots, key.num_values, offset)
+    });
+    table.set_metadata(TableMetadata {
+        objective: Some("win".to_string()),
+        generator: Some(format!("train {} {}", num_games, base_name)),
+        ..table.metadata().clone()
+    });
+    save_table(&table, output_path);
+    println!("Learning curve written to learning_curve.csv");
+}
+
+/// Tunes a strategy's parameters and prints the best setting with confidence intervals.
+fn tune(args: &[String]) {
+    let tunables = tunable_strategies();
+    let Some(tunable) = tunables.iter().find(|tunable| tunable.name == args[0]) else {
+        eprintln!(
+            "Strategy '{}' has no tunable parameters. Tunable strategies: {}",
+            args[0],
+            tunables
+                .iter()
+                .map(|tunable| tunable.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        );
+        std::process::exit(1);
+    };
+    let num_candidates = parse_positive_count(args.get(1).map(String::as_str), 16);
+    let games = parse_positive_count(args.get(2).map(String::as_str), 100_000);
+    let objective = match args.get(3) {
+        Some(arg) => Objective::parse(arg).unwrap_or_else(|| {
+            eprintln!("Unknown objective '{}'", arg);
+            std::process::exit(1);
+        }),
+        None => Objective::Win,
+    };
+    let seed = match args.get(4) {
+        Some(arg) => arg.parse().unwrap_or_else(|_| {
+            eprintln!("'{}' is not a seed", arg);
+            std::process::exit(1);
+        }),
+        None => 0,
+    };
+    let describe = |values: &[f64]| {
+        tunable
+            .parameters
+            .iter()
+            .zip(values)
+            .map(|(parameter, value)| match parameter.integer {
+                true => format!("{} = {}", parameter.name, value),
+                false => format!("{} = {:.4}", parameter.name, value),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let tuning = tuning::tune(
+        tunable,
+        num_candidates,
+        games,
+        objective,
+        seed,
+        |round, candidates, alive| {
+            let leader = &candidates[alive[0]];
+            println!(
+                "Round {}: {} candidates, {} games each, best {} with {} score {:.6} ± {:.6}",
+                round,
+                alive.len(),
+                leader.games,
+                describe(&leader.values),
+                objective,
+                leader.mean(),
+                leader.margin()
+            );
+        },
+    );
+
+    println!(
+        "{} by {} score (95% confidence intervals):",
+        tunable.name, objective
+    );
+    for candidate in &tuning.candidates {
+        println!(
+            "  {}: {:.6} ± {:.6} over {} games",
+            describe(&candidate.values),
+            candidate.mean(),
+            candidate.margin(),
+            candidate.games
+        );
+    }
+    if let Some((difference, margin)) = tuning.difference {
+        println!(
+            "The best beats the runner-up by {:.6} ± {:.6} on the same games",
+            difference, margin
+        );
+    }
+}
+
+/// Trains the weights of the parametric policy and compares it with the optimal policy.
+fn train_parametric(iterations: Option<&str>) {
+    let iterations = parse_count(iterations, 40);
+    let policy = parametric::train(
+        [1.0, 0.0, 0.0],
+        NUM_SLOTS,
+        NUM_VALUES,
+        iterations,
+        3,
+        0.05,
+        |iteration, policy, win_probability| {
+            println!(
+                "Iteration {}: weights {:?}, win rate {:.6}%",
+                iteration,
+                policy.weights(),
+                win_probability * 100.0
+            )
+        },
+    );
+
+    let win_probability = policy.win_probability(NUM_SLOTS, NUM_VALUES);
+    let trained = ParametricPolicy::new(ParametricPolicy::TRAINED, NUM_SLOTS)
+        .win_probability(NUM_SLOTS, NUM_VALUES);
+    let optimal = Solver::new(NUM_SLOTS, NUM_VALUES).win_probability(NUM_SLOTS, NUM_VALUES);
+    println!(
+        "{} weights {:?} win {:.6}% of games, {:.3}% of the optimal {:.6}%",
+        parametric::NUM_WEIGHTS,
+        policy.weights(),
+        win_probability * 100.0,
+        win_probability / optimal * 100.0,
+        optimal * 100.0
+    );
+    println!(
+        "The Parametric strategy's weights {:?} win {:.6}% of games, {:.3}% of the optimal",
+        ParametricPolicy::TRAINED,
+        trained * 100.0,
+        trained / optimal * 100.0
+    );
+}
+
+/// Prints the continuous-limit thresholds and compares the threshold policy with
+/// the optimal policy for numbers 0..values.
+fn continuous(values: Option<&str>) {
+    let num_values = parse_count(values, NUM_VALUES);
+    if num_values < NUM_SLOTS {
+        eprintln!("The range needs at least {} values", NUM_SLOTS);
+        std::process::exit(1);
+    }
+    let strategy = ThresholdStrategy::new(NUM_SLOTS);
+    let limit = ContinuousSolver::new(NUM_SLOTS);
+
+    let mut file = std::fs::File::create("continuous_thresholds.csv").unwrap();
+    writeln!(
+        file,
+        "Gap slots,Limit win probability,Placement,Upper fraction"
+    )
+    .unwrap();
+    for num_slots in 1..=NUM_SLOTS {
+        let thresholds = limit.thresholds(num_slots);
+        println!(
+            "{:>2} slots: win {:.8}, thresholds {}",
+            num_slots,
+            limit.win_probability(num_slots),
+            thresholds
+                .iter()
+                .map(|(placement, upper)| format!("{}<={:.6}", placement, upper))
+                .collect::<Vec<String>>()
+                .join(" ")
+        );
+        for (placement, upper) in thresholds {
+            writeln!(
+                file,
+                "{},{},{},{}",
+                num_slots,
+                limit.win_probability(num_slots),
+                placement,
+                upper
+            )
+            .unwrap();
+        }
+    }
+
+    let optimal = Solver::new(NUM_SLOTS, num_values);
+    let policy = |num_slots, num_values, offset| strategy.placement(num_slots, num_values, offset);
+    let threshold = Solver::evaluate(NUM_SLOTS, num_values, policy);
+    let mut suboptimal = 0;
+    for num_slots in 2..=NUM_SLOTS {
+        for gap_values in num_slots..=num_values {
+            for offset in 0..gap_values {
+                let best = optimal.best_placement(num_slots, gap_values, offset).1;
+                let chosen = optimal.placement_win_probability(
+                    num_slots,
+                    gap_values,
+                    offset,
+                    policy(num_slots, gap_values, offset),
+                );
+                if chosen < best * (1.0 - 1e-12) {
+                    suboptimal += 1;
+                }
+            }
+        }
+    }
+
+    println!("Numbers 0..{}:", num_values);
+    println!(
+        "  Optimal (LookupTable) win rate: {:.10}%",
+        optimal.win_probability(NUM_SLOTS, num_values) * 100.0
+    );
+    println!(
+        "  Threshold win rate:             {:.10}% ({:.4}% of optimal)",
+        threshold.win_probability(NUM_SLOTS, num_values) * 100.0,
+        threshold.win_probability(NUM_SLOTS, num_values)
+            / optimal.win_probability(NUM_SLOTS, num_values)
+            * 100.0
+    );
+    println!(
+        "  Continuous limit win rate:      {:.10}%",
+        limit.win_probability(NUM_SLOTS) * 100.0
+    );
+    println!("  Suboptimal threshold decisions: {}", suboptimal);
+    println!("Thresholds written to continuous_thresholds.csv");
+}
+
+/// Runs one of the `table` subcommands.
+fn table_command(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("export") if args.len() == 3 => {
+            let strategy = strategy_by_name(&args[1]);
+            let mut table = LookupTable::from_strategy(strategy.as_ref(), NUM_SLOTS, NUM_VALUES);
+            table.set_metadata(TableMetadata {
+                generator: Some(format!("table export {}", args[1])),
+                ..table.metadata().clone()
+            });
+            save_table(&table, &args[2]);
+        }
+        Some("diff") if args.len() == 3 => table_diff(&args[1], &args[2]),
+        Some("check") if args.len() == 2 => table_check(&args[1]),
+        Some("convert") if args.len() == 3 => save_table(&load_table(&args[1]), &args[2]),
+        Some("solve") if args.len() == 2 => table_solve(&args[1]),
+        Some("solve") if args.len() == 3 => {
+            let objective = Objective::parse(&args[2])
+                .filter(|objective| !matches!(objective, Objective::AtLeast(count) if *count == 0 || *count > NUM_SLOTS))
+                .unwrap_or_else(|| {
+                    eprintln!("Unknown objective '{}'", args[2]);
+                    std::process::exit(1);
+                });
+            match objective {
+                Objective::Win => table_solve(&args[1]),
+                _ => table_optimise(objective, &args[1]),
+            }
+        }
+        Some("solve-exact") if args.len() == 2 || args.len() == 3 => {
+            let tie_break = args
+                .get(2)
+                .map_or(Some(TieBreak::Lowest), |arg| TieBreak::parse(arg));
+            let tie_break = tie_break.unwrap_or_else(|| {
+                eprintln!("Unknown tie-break rule '{}'", args[2]);
+                std::process::exit(1);
+            });
+            table_solve_exact(&args[1], tie_break);
+        }
+        Some("cache") if args.len() == 2 || args.len() == 3 => {
+            table_cache(&args[1], args.get(2).map(String::as_str))
+        }
+        Some("ties") if args.len() >= 2 => table_ties(&args[1..]),
+        Some("annotate") if args.len() == 3 => table_annotate(&args[1], &args[2]),
+        Some("explain") if args.len() == 5 => table_explain(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Writes the optimal policy as a lookup table annotated with its win probabilities.
+fn table_solve(output_path: &str) {
+    let solver = Solver::new(NUM_SLOTS, NUM_VALUES);
+    let mut table = LookupTable::from_policy(NUM_SLOTS, NUM_VALUES, |key, offset| {
+        solver
+            .best_placement(key.num_slots, key.num_values, offset)
+            .0
+    });
+    table.set_win_probabilities(|key| solver.win_probability(key.num_slots, key.num_values));
+    table.set_metadata(TableMetadata {
+        objective: Some("win".to_string()),
+        generator: Some("table solve".to_string()),
+        ..table.metadata().clone()
+    });
+    save_table(&table, output_path);
+}
+
+/// Plays games with a `SolverStrategy` and saves the gaps it solved along the way.
+fn table_cache(output_path: &str, num_games: Option<&str>) {
+    let num_games = parse_count(num_games, 1000);
+    let solver = Arc::new(SolverStrategy::new(NUM_SLOTS));
+    let strategies: Vec<(String, Arc<dyn Strategy>)> = vec![("Solver".to_string(), solver.clone())];
+    let histograms = run_simulations_multi(&strategies, num_games);
+    println!(
+        "Win rate over {} games: {:.4}%",
+        num_games,
+        histograms[0].1[NUM_SLOTS] as f64 / num_games as f64 * 100.0
+    );
+
+    let mut table = solver.to_table();
+    table.set_metadata(TableMetadata {
+        config: Some(GAME_CONFIG),
+        objective: Some("win".to_string()),
+        generator: Some(format!("table cache {}", num_games)),
+    });
+    save_table(&table, output_path);
+}
+
+/// Writes the optimal policy found with exact arithmetic as a lookup table.
+fn table_solve_exact(output_path: &str, tie_break: TieBreak) {
+    let solver = ExactSolver::new(NUM_SLOTS, NUM_VALUES);
+    println!(
+        "Winning draw orders: {} ({:.10}%)",
+        solver.win_count(NUM_SLOTS, NUM_VALUES),
+        solver.win_probability(NUM_SLOTS, NUM_VALUES) * 100.0
+    );
+
+    let ties = AtomicUsize::new(0);
+    let mut table = LookupTable::from_policy(NUM_SLOTS, NUM_VALUES, |key, offset| {
+        let best = solver.best_placements(key.num_slots, key.num_values, offset);
+        if best.len() > 1 {
+            ties.fetch_add(1, Ordering::Relaxed);
+        }
+        tie_break.choose(key.num_slots, &best)
+    });
+    println!("{} decisions are exact ties", ties.into_inner());
+    table.set_win_probabilities(|key| solver.win_probability(key.num_slots, key.num_values));
+    table.set_metadata(TableMetadata {
+        objective: Some("win".to_string()),
+        generator: Some(format!("table solve-exact {}", tie_break)),
+        ..table.metadata().clone()
+    });
+    save_table(&table, output_path);
+}
+
+/// Counts, for each table, the exact ties it decides the way each tie-break rule
+/// does, and the decisions that are not optimal at all.
+fn table_ties(file_paths: &[String]) {
+    let tables: Vec<LookupTable> = file_paths.iter().map(|path| load_table(path)).collect();
+    let solver = ExactSolver::new(NUM_SLOTS, NUM_VALUES);
+
+    // Per table: ties decided like each rule, ties decided like none, and suboptimal decisions.
+    let mut counts = vec![([0usize; TieBreak::ALL.len()], 0usize, 0usize); tables.len()];
+    let mut num_ties = 0;
+    for num_slots in 2..=NUM_SLOTS {
+        for num_values in num_slots..=NUM_VALUES {
+            for offset in 0..num_values {
+                let best = solver.best_placements(num_slots, num_values, offset);
+                if best.len() > 1 {
+                    num_ties += 1;
+                }
+                for (table, counts) in tables.iter().zip(&mut counts) {
+                    let placement = table.placement(num_slots, num_values, offset as i32);
+                    if !placement.is_some_and(|placement| best.contains(&placement)) {
+                        counts.2 += 1;
+                    } else if best.len() > 1 {
+                        let mut matched = false;
+                        for (rule, tie_break) in TieBreak::ALL.iter().enumerate() {
+                            if placement == Some(tie_break.choose(num_slots, &best)) {
+                                counts.0[rule] += 1;
+                                matched = true;
+                            }
+                        }
+                        if !matched {
+                            counts.1 += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    println!("{} decisions are exact ties", num_ties);
+    for (file_path, (by_rule, unmatched, suboptimal)) in file_paths.iter().zip(&counts) {
+        let by_rule: Vec<String> = TieBreak::ALL
+            .iter()
+            .zip(by_rule)
+            .map(|(tie_break, count)| format!("{} {}", tie_break, count))
+            .collect();
+        println!(
+            "{}: ties decided like {}, like no rule {}; suboptimal decisions {}",
+            file_path,
+            by_rule.join(", "),
+            unmatched,
+            suboptimal
+        );
+    }
+}
+
+/// Writes a locally optimal lookup table for an objective other than winning, found
+/// by improving the win-optimal policy.
+fn table_optimise(objective: Objective, output_path: &str) {
+    let (policy, values) =
+        objective::optimise(objective, NUM_SLOTS, NUM_VALUES, 100, |iteration, value| {
+            println!("Iteration {}: {} = {:.10}", iteration, objective, value)
+        });
+    println!(
+        "Expected placements: {:.10}, win rate: {:.10}%",
+        values.expected_placements(),
+        values.at_least(NUM_SLOTS) * 100.0
+    );
+    for count in 1..=NUM_SLOTS {
+        println!(
+            "  P(at least {:>2} placed) = {:.10}",
+            count,
+            values.at_least(count)
+        );
+    }
+
+    let mut table = LookupTable::from_policy(NUM_SLOTS, NUM_VALUES, |key, offset| {
+        policy.placement(key.num_slots, key.num_values, offset)
+    });
+    table.set_metadata(TableMetadata {
+        objective: Some(objective.to_string()),
+        generator: Some(format!("table solve {} (local search)", objective)),
+        ..table.metadata().clone()
+    });
+    println!(
+        "Found by local search, so the table is not known to be the best for {}",
+        objective
+    );
+    save_table(&table, output_path);
+}
+
+/// Annotates a lookup table with the exact win probability of every gap shape when
+/// the table is followed.
+fn table_annotate(file_path: &str, output_path: &str) {
+    let mut table = load_table(file_path);
+    let covers_game = (1..=NUM_SLOTS).all(|num_slots| {
+        (num_slots..=NUM_VALUES)
+            .all(|num_values| table.placement(num_slots, num_values, 0).is_some())
+    });
+    if !covers_game {
+        eprintln!(
+            "{} does not cover every gap shape, so its win probabilities are unknown",
+            file_path
+        );
+        std::process::exit(1);
+    }
+
+    let values = Solver::evaluate(NUM_SLOTS, NUM_VALUES, |num_slots, num_values, offset| {
+        table
+            .placement(num_slots, num_values, offset as i32)
+            .unwrap()
+    });
+    println!(
+        "Win rate: {:.10}%",
+        values.win_probability(NUM_SLOTS, NUM_VALUES) * 100.0
+    );
+    table.set_win_probabilities(|key| values.win_probability(key.num_slots, key.num_values));
+    save_table(&table, output_path);
+}
+
+/// Prints the win probability of every placement of one number, using the table's
+/// own win probabilities or else evaluating the table on the gap.
+fn table_explain(args: &[String]) {
+    let table = load_table(&args[0]);
+    let parse = |arg: &str, name: &str| {
+        arg.parse::<usize>().unwrap_or_else(|_| {
+            eprintln!("{} '{}' is not a non-negative number", name, arg);
+            std::process::exit(1);
+        })
+    };
+    let num_slots = parse(&args[1], "slots");
+    let num_values = parse(&args[2], "values");
+    let offset = parse(&args[3], "offset");
+    if num_slots == 0 || num_slots > num_values || offset >= num_values {
+        eprintln!(
+            "A gap with {} slots and {} values has no offset {}",
+            num_slots, num_values, offset
+        );
+        std::process::exit(1);
+    }
+    let Some(chosen) = table.placement(num_slots, num_values, offset as i32) else {
+        eprintln!(
+            "{} has no entry for gap with {} slots and {} values",
+            args[0], num_slots, num_values
+        );
+        std::process::exit(1);
+    };
+
+    let evaluated = (!table.has_win_probabilities()).then(|| {
+        Solver::evaluate(num_slots, num_values, |s, v, o| {
+            table.placement(s, v, o as i32).unwrap_or(0)
+        })
+    });
+    let placement_win = |placement: usize| match &evaluated {
+        Some(values) => values.placement_win_probability(num_slots, num_values, offset, placement),
+        None => table
+            .placement_win_probability(num_slots, num_values, offset, placement)
+            .unwrap_or(f64::NAN),
+    };
+    println!(
+        "Gap with {} slots and {} values, number at offset {}:",
+        num_slots, num_values, offset
+    );
+    for placement in 0..num_slots {
+        println!(
+            "  slot {:>2}: {:.6}%{}",
+            placement,
+            placement_win(placement) * 100.0,
+            if placement == chosen {
+                "  <- table"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+/// Prints the slot scores a strategy gives a gap, the slot it chooses and how close
+/// the runner-up came, next to the exact win probability of every placement.
+fn explain(args: &[String]) {
+    let strategy = strategy_by_name(&args[0]);
+    let parse = |arg: &str, name: &str| {
+        arg.parse::<usize>().unwrap_or_else(|_| {
+            eprintln!("{} '{}' is not a non-negative number", name, arg);
+            std::process::exit(1);
+        })
+    };
+    let num_slots = parse(&args[1], "slots");
+    let num_values = parse(&args[2], "values");
+    let offset = parse(&args[3], "offset");
+    if num_slots == 0
+        || num_slots > NUM_SLOTS
+        || num_slots > num_values
+        || num_values > NUM_VALUES
+        || offset >= num_values
+    {
+        eprintln!(
+            "A gap with {} slots and {} values has no offset {} in this game",
+            num_slots, num_values, offset
+        );
+        std::process::exit(1);
+    }
+
+    // Ask on a board holding only the gap and its upper neighbour, as table export does.
+    let gap = Gap {
+        lower: LOWER_BOUND,
+        upper: LOWER_BOUND + num_values as i32 + 1,
+        first_index: 0,
+        last_index: num_slots - 1,
+    };
+    let mut board = vec![None; NUM_SLOTS];
+    if num_slots < NUM_SLOTS && gap.upper < UPPER_BOUND {
+        board[num_slots] = Some(gap.upper);
+    }
+    let number = gap.lower + 1 + offset as i32;
+    let chosen = choose_placement(
+        strategy.as_ref(),
+        &gap,
+        number,
+        &board,
+        &mut StdRng::seed_from_u64(0),
+    );
+    let scores = strategy.slot_scores(
+        gap.lower,
+        gap.upper,
+        gap.first_index,
+        gap.last_index,
+        number,
+        &board,
+    );
+    let solver = Solver::new(num_slots, num_values);
+
+    println!(
+        "Gap with {} slots and {} values, number at offset {}, for {}:",
+        num_slots, num_values, offset, args[0]
+    );
+    let total: f64 = scores.iter().flatten().sum();
+    for placement in 0..num_slots {
+        let score = match &scores {
+            Some(scores) => format!(
+                "score {:.6e} ({:>6.2}%), ",
+                scores[placement],
+                scores[placement] / total * 100.0
+            ),
+            None => String::new(),
+        };
+        println!(
+            "  slot {:>2}: {}win {:.6}%{}",
+            placement,
+            score,
+            solver.placement_win_probability(num_slots, num_values, offset, placement) * 100.0,
+            if placement == chosen {
+                "  <- chosen"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let Some(scores) = scores else {
+        println!("{} does not score slots", args[0]);
+        return;
+    };
+    let best = highest_score(&scores);
+    let runner_up = (0..num_slots)
+        .filter(|&placement| placement != best)
+        .max_by(|&a, &b| scores[a].total_cmp(&scores[b]));
+    if let Some(runner_up) = runner_up {
+        println!(
+            "Runner-up slot {} scores {:.4}% of the best, slot {}",
+            runner_up,
+            scores[runner_up] / scores[best] * 100.0,
+            best
+        );
+    }
+}
+
+/// Prints every problem with a lookup table, exiting with an error if there are any.
+fn table_check(file_path: &str) {
+    let file = table::read_table(file_path).unwrap_or_else(|err| {
+        eprintln!("Failed to read lookup table: {}", err);
+        std::process::exit(1);
+    });
+    let (metadata, rows) = (&file.metadata, &file.rows);
+    println!("{}: {}", file_path, metadata);
+    let mut problems: Vec<String> = table::check_rows(rows)
+        .iter()
+        .chain(&table::check_values(&file.values))
+        .map(ToString::to_string)
+        .collect();
+    if !metadata.fits(GAME_CONFIG) {
+        problems.push(format!(
+            "table was built for a game other than {} slots and {} values",
+            NUM_SLOTS, NUM_VALUES
+        ));
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("{}: {} rows, no problems", file_path, rows.len());
+    } else {
+        println!("{}: {} problems", file_path, problems.len());
+        std::process::exit(1);
+    }
+}
+
+/// Lists every decision where two lookup tables differ, with its estimated effect on the win rate.
+fn table_diff(path_a: &str, path_b: &str) {
+    let tables = [load_table(path_a), load_table(path_b)];
+    let differences = tables[0].diff(&tables[1], NUM_SLOTS, NUM_VALUES);
+    println!("{} decisions differ", differences.len());
+    if differences.is_empty() {
+        return;
+    }
+
+    let complete = differences
+        .iter()
+        .all(|difference| difference.placements.iter().all(Option::is_some));
+    if !complete {
+        let missing = differences
+            .iter()
+            .filter(|difference| difference.placements.iter().any(Option::is_none))
+            .count();
+        println!(
+            "{} of them are gaps missing from one table, so the win rates cannot be compared",
+            missing
+        );
+    }
+
+    // Score each difference with the win probabilities of playing table a.
+    let policy = |table: &LookupTable, num_slots: usize, num_values: usize, offset: usize| {
+        table
+            .placement(num_slots, num_values, offset as i32)
+            .unwrap_or(0)
+    };
+    let policy_a =
+        |num_slots, num_values, offset| policy(&tables[0], num_slots, num_values, offset);
+    let policy_b =
+        |num_slots, num_values, offset| policy(&tables[1], num_slots, num_values, offset);
+    let values = Solver::evaluate(NUM_SLOTS, NUM_VALUES, policy_a);
+    let sensitivity = values.sensitivities(policy_a);
+
+    let mut file = std::fs::File::create("table_diff.csv").unwrap();
+    writeln!(
+        file,
+        "Gap slots,Gap values,Offset,Placement a,Placement b,Gap win a,Gap win b,Win rate impact"
+    )
+    .unwrap();
+
+    let mut impacts = Vec::new();
+    for difference in &differences {
+        let key = difference.key;
+        let format_placement =
+            |placement: Option<usize>| placement.map_or("-".to_string(), |p| p.to_string());
+        let wins: Vec<Option<f64>> = difference
+            .placements
+            .iter()
+            .map(|placement| {
+                placement.map(|placement| {
+                    values.placement_win_probability(
+                        key.num_slots,
+                        key.num_values,
+                        difference.offset,
+                        placement,
+                    )
+                })
+            })
+            .collect();
+        let impact = match (wins[0], wins[1]) {
+            // Placements that tie exactly differ only by rounding in f64.
+            (Some(win_a), Some(win_b)) if (win_b - win_a).abs() <= win_a.max(win_b) * 1e-12 => {
+                Some(0.0)
+            }
+            (Some(win_a), Some(win_b)) => {
+                let weight = sensitivity[key.num_slots * (NUM_VALUES + 1) + key.num_values];
+                Some(weight * (win_b - win_a) / key.num_values as f64)
+            }
+            _ => None,
+        };
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{}",
+            key.num_slots,
+            key.num_values,
+            difference.offset,
+            format_placement(difference.placements[0]),
+            format_placement(difference.placements[1]),
+            wins[0].map_or(String::new(), |win| win.to_string()),
+            wins[1].map_or(String::new(), |win| win.to_string()),
+            impact.map_or(String::new(), |impact| impact.to_string())
+        )
+        .unwrap();
+        if let Some(impact) = impact {
+            impacts.push((impact, difference));
+        }
+    }
+
+    // Show the differences that matter most.
+    impacts.sort_by(|a, b| b.0.abs().total_cmp(&a.0.abs()));
+    for (impact, difference) in impacts.iter().take(10) {
+        println!(
+            "  {} slots, {} values, offset {}: a places at {}, b at {}, win rate impact {:+.3e}",
+            difference.key.num_slots,
+            difference.key.num_values,
+            difference.offset,
+            difference.placements[0].unwrap(),
+            difference.placements[1].unwrap(),
+            impact
+        );
+    }
+
+    if complete {
+        let win_a = values.win_probability(NUM_SLOTS, NUM_VALUES);
+        let win_b = Solver::evaluate(NUM_SLOTS, NUM_VALUES, policy_b)
+            .win_probability(NUM_SLOTS, NUM_VALUES);
+        // The evaluation is in f64, so differences below its rounding are not real.
+        let difference = if (win_b - win_a).abs() <= win_a.max(win_b) * 1e-12 {
+            0.0
+        } else {
+            win_b - win_a
+        };
+        println!(
+            "Win rate (f64, to about 1e-12 relative): a {:.10}%, b {:.10}%, difference {:+.3e}",
+            win_a * 100.0,
+            win_b * 100.0,
+            difference
+        );
+    }
+    println!("Output written to table_diff.csv");
+}
 
+/// Writes the summary and histogram csv files for a set of games.
+fn write_results(histograms: &[(String, [usize; NUM_SLOTS + 1])], num_simulations: usize) {
     // Open csv output file.
     let mut file = std::fs::File::create("output.csv").unwrap();
 
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::engine::{draw_more_numbers, feasible_placements, find_valid_gap, play_out, Gap};
+use crate::objective::Objective;
+use crate::strategy::Strategy;
+
+/// A node of the search tree. Its statistics are the scores of the games played
+/// through it, from the point of view of the decision that led to it.
+struct Node {
+    visits: u32,
+    total_score: f64,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    /// A drawn number waiting to be placed, with a child for each slot of its gap
+    /// once that slot has been tried.
+    Decision { children: Vec<Option<usize>> },
+    /// A board waiting for the next draw, with a child for each kind of draw seen
+    /// there. Draws are told apart by `draw_key`, since the exact number almost
+    /// never comes up twice.
+    Chance {
+        children: HashMap<(usize, usize), usize>,
+    },
+}
+
+impl Node {
+    fn new(kind: NodeKind) -> Self {
+        Self {
+            visits: 0,
+            total_score: 0.0,
+            kind,
+        }
+    }
+
+    fn mean_score(&self) -> f64 {
+        self.total_score / self.visits as f64
+    }
+}
+
+/// The size of `gap` and the offset of `number` in it.
+fn gap_shape(gap: &Gap, number: i32) -> (usize, usize, usize) {
+    (
+        gap.last_index - gap.first_index + 1,
+        (gap.upper - gap.lower - 1) as usize,
+        (number - gap.lower - 1) as usize,
+    )
+}
+
+/// The slots of `gap` worth trying for `number`: those that can still fill the gap,
+/// or every slot once it cannot be filled anyway.
+fn placements(gap: &Gap, number: i32) -> std::ops::RangeInclusive<usize> {
+    let (num_slots, num_values, offset) = gap_shape(gap, number);
+    let feasible = feasible_placements(num_slots, num_values, offset);
+    if feasible.is_empty() {
+        0..=num_slots - 1
+    } else {
+        feasible
+    }
+}
+
+/// Groups a draw by its gap and by the slot it would take if the gap's values were
+/// spread evenly over its slots, so that similar draws share a subtree.
+fn draw_key(gap: &Gap, number: i32) -> (usize, usize) {
+    let (num_slots, num_values, offset) = gap_shape(gap, number);
+    (gap.first_index, offset * num_slots / num_values)
+}
+
+/// Monte Carlo tree search for the slot of `gap` on `board` to place `number` in.
+///
+/// Decision nodes pick among the slots that can still fill their gap by UCB1 with
+/// the given `exploration` constant, chance nodes draw the next number at random
+/// and follow the child for its `draw_key`, and each iteration that reaches a slot
+/// not tried before plays the rest of the game out with `base`. Games are scored by
+/// `objective`, and the slot searched most often is chosen.
+#[allow(clippy::too_many_arguments)]
+pub fn search(
+    board: &[Option<i32>],
+    gap: &Gap,
+    number: i32,
+    base: &dyn Strategy,
+    objective: Objective,
+    iterations: usize,
+    exploration: f64,
+    rng: &mut impl Rng,
+) -> usize {
+    let num_slots = board.len();
+    let mut nodes = vec![Node::new(NodeKind::Decision {
+        children: vec![None; gap.last_index - gap.first_index + 1],
+    })];
+    let mut working = board.to_vec();
+    let mut path = Vec::new();
+
+    for _ in 0..iterations {
+        working.copy_from_slice(board);
+        path.clear();
+        path.push(0);
+        let mut node = 0;
+        let (mut gap, mut number) = (gap.clone(), number);
+
+        let placed = loop {
+            let parent_visits = nodes[node].visits;
+            match &nodes[node].kind {
+                NodeKind::Decision { children } => {
+                    let choices = placements(&gap, number);
+                    let untried = choices.clone().find(|&choice| children[choice].is_none());
+                    let choice = untried.unwrap_or_else(|| {
+                        let log_visits = (parent_visits as f64).ln();
+                        let ucb = |choice: usize| {
+                            let child = &nodes[children[choice].unwrap()];
+                            child.mean_score()
+                                + exploration * (log_visits / child.visits as f64).sqrt()
+                        };
+                        choices.max_by(|&a, &b| ucb(a).total_cmp(&ucb(b))).unwrap()
+                    });
+                    working[gap.first_index + choice] = Some(number);
+
+                    if untried.is_some() {
+                        // Expand the new slot and play the rest of the game out.
+                        let child = nodes.len();
+                        nodes.push(Node::new(NodeKind::Chance {
+                            children: HashMap::new(),
+                        }));
+                        if let NodeKind::Decision { children, .. } = &mut nodes[node].kind {
+                            children[choice] = Some(child);
+                        }
+                        path.push(child);
+
+                        let drawn: Vec<i32> = working.iter().flatten().copied().collect();
+                        let numbers = draw_more_numbers(&drawn, num_slots - drawn.len(), rng);
+                        break drawn.len() + play_out(base, &mut working, &numbers, rng);
+                    }
+                    node = children[choice].unwrap();
+                    path.push(node);
+                }
+                NodeKind::Chance { children } => {
+                    let drawn: Vec<i32> = working.iter().flatten().copied().collect();
+                    if drawn.len() == num_slots {
+                        break num_slots;
+                    }
+                    let next = draw_more_numbers(&drawn, 1, rng)[0];
+                    let Some(next_gap) = find_valid_gap(&working, next) else {
+                        break drawn.len();
+                    };
+                    (gap, number) = (next_gap, next);
+                    let key = draw_key(&gap, number);
+                    node = match children.get(&key) {
+                        Some(&child) => child,
+                        None => {
+                            let child = nodes.len();
+                            nodes.push(Node::new(NodeKind::Decision {
+                                children: vec![None; gap.last_index - gap.first_index + 1],
+                            }));
+                            if let NodeKind::Chance { children } = &mut nodes[node].kind {
+                                children.insert(key, child);
+                            }
+                            child
+                        }
+                    };
+                    path.push(node);
+                }
+            }
+        };
+
+        let score = objective.score(placed, num_slots);
+        for &index in &path {
+            nodes[index].visits += 1;
+            nodes[index].total_score += score;
+        }
+    }
+
+    let NodeKind::Decision { children, .. } = &nodes[0].kind else {
+        unreachable!("the root is a decision node");
+    };
+    let visits = |choice: usize| children[choice].map_or(0, |child| nodes[child].visits);
+    gap.first_index
+        + placements(gap, number)
+            .max_by_key(|&choice| (visits(choice), std::cmp::Reverse(choice)))
+            .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::NUM_SLOTS;
+    use crate::solver::Solver;
+    use crate::strategies::OptimalWinStrategy;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// A board whose first `gap_slots` slots are empty and whose other slots hold the
+    /// numbers from `upper` up, with the gap they leave.
+    fn board(gap_slots: usize, upper: i32) -> (Vec<Option<i32>>, Gap) {
+        let board = (0..NUM_SLOTS)
+            .map(|slot| (slot >= gap_slots).then(|| upper + (slot - gap_slots) as i32))
+            .collect();
+        let gap = Gap {
+            lower: -1,
+            upper,
+            first_index: 0,
+            last_index: gap_slots - 1,
+        };
+        (board, gap)
+    }
+
+    fn search_with(board: &[Option<i32>], gap: &Gap, number: i32, iterations: usize) -> usize {
+        let mut rng = StdRng::seed_from_u64(0);
+        search(
+            board,
+            gap,
+            number,
+            &OptimalWinStrategy,
+            Objective::Win,
+            iterations,
+            2.0_f64.sqrt(),
+            &mut rng,
+        )
+    }
+
+    #[test]
+    fn picks_the_only_feasible_slot() {
+        // Three slots for the values 0, 1 and 2: the 1 can only go in the middle.
+        let (board, gap) = board(3, 3);
+        for iterations in [0, 1, 100] {
+            assert_eq!(search_with(&board, &gap, 1, iterations), 1);
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_solver_on_a_small_gap() {
+        // The board's other slots hold the top numbers, so nearly every draw left
+        // falls in the gap and the game is close to the three-slot one.
+        let upper = 1000 - (NUM_SLOTS - 3) as i32;
+        let (board, gap) = board(3, upper);
+        let solver = Solver::new(3, upper as usize);
+        for number in [50, 300, 500, 700, 950] {
+            let (best, _) = solver.best_placement(3, upper as usize, number as usize);
+            assert_eq!(
+                search_with(&board, &gap, number, 3000),
+                best,
+                "number {}",
+                number
+            );
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::fmt;
+
+use rayon::prelude::*;
+
+use crate::solver::{binomial_table, Solver};
+
+/// What a placement policy tries to maximise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// The probability of placing every number.
+    Win,
+    /// The expected number of numbers placed before the game is lost.
+    ExpectedPlacements,
+    /// The probability of placing at least this many numbers.
+    AtLeast(usize),
+}
+
+impl Objective {
+    /// Parses `win`, `placements` or `at-least:<k>`.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "win" => Some(Objective::Win),
+            "placements" => Some(Objective::ExpectedPlacements),
+            _ => text
+                .strip_prefix("at-least:")
+                .and_then(|count| count.parse().ok())
+                .map(Objective::AtLeast),
+        }
+    }
+
+    /// The objective's value for one game that placed `placed` of `num_slots`
+    /// numbers, scaled so that placing every number scores 1.
+    pub fn score(&self, placed: usize, num_slots: usize) -> f64 {
+        let weights = self.weights(num_slots);
+        weights[..=placed].iter().sum::<f64>() / weights.iter().sum::<f64>()
+    }
+
+    /// The objective as a combination of the probabilities of placing at least
+    /// `k` numbers, for `k` from 0 to `max_slots`.
+    fn weights(&self, max_slots: usize) -> Vec<f64> {
+        let mut weights = vec![0.0; max_slots + 1];
+        match *self {
+            Objective::Win => weights[max_slots] = 1.0,
+            Objective::ExpectedPlacements => weights[1..].fill(1.0),
+            Objective::AtLeast(count) => weights[count.min(max_slots)] = 1.0,
+        }
+        weights
+    }
+}
+
+impl fmt::Display for Objective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Objective::Win => write!(f, "win"),
+            Objective::ExpectedPlacements => write!(f, "placements"),
+            Objective::AtLeast(count) => write!(f, "at-least:{}", count),
+        }
+    }
+}
+
+/// A gap-local placement policy: a placement for every offset of every gap shape
+/// with at least as many values as slots.
+#[derive(Clone)]
+pub struct Policy {
+    max_slots: usize,
+    max_values: usize,
+    /// The placements of gap shape `(num_slots, num_values)` start at
+    /// `starts[num_slots * (max_values + 1) + num_values]`.
+    starts: Vec<usize>,
+    placements: Vec<u8>,
+}
+
+impl Policy {
+    /// Builds a policy from the placement `policy` returns for each gap shape and offset.
+    pub fn from_fn(
+        max_slots: usize,
+        max_values: usize,
+        policy: impl Fn(usize, usize, usize) -> usize,
+    ) -> Self {
+        let mut starts = Vec::with_capacity((max_slots + 1) * (max_values + 1));
+        let mut placements = Vec::new();
+        for num_slots in 0..=max_slots {
+            for num_values in 0..=max_values {
+                starts.push(placements.len());
+                if num_slots >= 1 && num_values >= num_slots {
+                    placements.extend(
+                        (0..num_values).map(|offset| policy(num_slots, num_values, offset) as u8),
+                    );
+                }
+            }
+        }
+        Self {
+            max_slots,
+            max_values,
+            starts,
+            placements,
+        }
+    }
+
+    fn start(&self, num_slots: usize, num_values: usize) -> usize {
+        self.starts[num_slots * (self.max_values + 1) + num_values]
+    }
+
+    /// The placement (0-indexed within the gap) of the number at `offset`.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: usize) -> usize {
+        self.placements[self.start(num_slots, num_values) + offset] as usize
+    }
+}
+
+/// Exact placement-count probabilities of a gap-local policy.
+///
+/// The game places at least `k` numbers exactly when the first `k` draws can all be
+/// placed, and under a gap-local policy that only depends on those draws. They are
+/// a random subset of the values in a random order, so like the win probability the
+/// chance of fitting them splits over the two gaps each placement creates, with a
+/// hypergeometric number of the remaining draws landing on each side.
+pub struct PlacementSolver {
+    max_slots: usize,
+    max_values: usize,
+    /// `binomials[n * (max_slots + 1) + k]` is "n choose k".
+    binomials: Vec<f64>,
+    /// `fit[(num_slots * (max_values + 1) + num_values) * (max_slots + 1) + count]` is
+    /// the probability that `count` numbers drawn from the gap can all be placed.
+    fit: Vec<f64>,
+}
+
+impl PlacementSolver {
+    /// Computes the placement-count probabilities of every gap shape under `policy`.
+    pub fn evaluate(policy: &Policy) -> Self {
+        let max_slots = policy.max_slots;
+        let max_values = policy.max_values;
+        let row_len = (max_values + 1) * (max_slots + 1);
+        let mut solver = Self {
+            max_slots,
+            max_values,
+            binomials: binomial_table(max_values, max_slots),
+            fit: vec![0.0; (max_slots + 1) * row_len],
+        };
+
+        // No draws always fit, and nothing else fits an empty gap.
+        for num_slots in 0..=max_slots {
+            for num_values in 0..=max_values {
+                let index = solver.index(num_slots, num_values, 0);
+                solver.fit[index] = 1.0;
+            }
+        }
+
+        // Each gap only depends on gaps with fewer slots, so a whole row can be solved at once.
+        for num_slots in 1..=max_slots {
+            let row: Vec<f64> = (0..=max_values)
+                .into_par_iter()
+                .flat_map_iter(|num_values| {
+                    let mut fit = vec![0.0; max_slots + 1];
+                    fit[0] = 1.0;
+                    if num_values >= num_slots {
+                        for offset in 0..num_values {
+                            let placement = policy.placement(num_slots, num_values, offset);
+                            solver.accumulate(
+                                num_slots,
+                                num_values,
+                                offset,
+                                placement,
+                                |count, weight| fit[count] += weight,
+                            );
+                        }
+                        for (count, fit) in fit.iter_mut().enumerate().skip(1) {
+                            *fit /= num_values as f64 * solver.binom(num_values - 1, count - 1);
+                        }
+                    }
+                    fit
+                })
+                .collect();
+            let start = num_slots * row_len;
+            solver.fit[start..start + row_len].copy_from_slice(&row);
+        }
+
+        solver
+    }
+
+    fn index(&self, num_slots: usize, num_values: usize, count: usize) -> usize {
+        (num_slots * (self.max_values + 1) + num_values) * (self.max_slots + 1) + count
+    }
+
+    fn binom(&self, n: usize, k: usize) -> f64 {
+        if k > n {
+            0.0
+        } else {
+            self.binomials[n * (self.max_slots + 1) + k]
+        }
+    }
+
+    /// Calls `add(count, weight)` for every way the other draws of a gap can split
+    /// around the number at `offset` in `placement`, where `weight` is the number of
+    /// ways to choose them times the probability that both sides fit.
+    fn accumulate(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+        placement: usize,
+        mut add: impl FnMut(usize, f64),
+    ) {
+        let right_slots = num_slots - 1 - placement;
+        let right_values = num_values - 1 - offset;
+        for left in 0..=placement.min(offset) {
+            let left_weight =
+                self.binom(offset, left) * self.fit_probability(placement, offset, left);
+            if left_weight == 0.0 {
+                continue;
+            }
+            for right in 0..=right_slots.min(right_values) {
+                add(
+                    left + right + 1,
+                    left_weight
+                        * self.binom(right_values, right)
+                        * self.fit_probability(right_slots, right_values, right),
+                );
+            }
+        }
+    }
+
+    /// The probability that `count` numbers drawn from a gap can all be placed.
+    pub fn fit_probability(&self, num_slots: usize, num_values: usize, count: usize) -> f64 {
+        if count > num_slots || count > num_values {
+            return 0.0;
+        }
+        self.fit[self.index(num_slots, num_values, count)]
+    }
+
+    /// The probability that a whole game places at least `count` numbers.
+    pub fn at_least(&self, count: usize) -> f64 {
+        self.fit_probability(self.max_slots, self.max_values, count)
+    }
+
+    /// The expected number of numbers a whole game places.
+    pub fn expected_placements(&self) -> f64 {
+        (1..=self.max_slots).map(|count| self.at_least(count)).sum()
+    }
+
+    /// The value of `objective` for a whole game.
+    pub fn value(&self, objective: Objective) -> f64 {
+        objective
+            .weights(self.max_slots)
+            .iter()
+            .enumerate()
+            .map(|(count, weight)| weight * self.at_least(count))
+            .sum()
+    }
+
+    /// How much the value of `objective` changes per unit change in each fit
+    /// probability, indexed like `fit`, when every placement is made by `policy`.
+    fn sensitivities(&self, objective: Objective, policy: &Policy) -> Vec<f64> {
+        let mut sensitivity = vec![0.0; self.fit.len()];
+        let top = self.index(self.max_slots, self.max_values, 0);
+        sensitivity[top..top + self.max_slots + 1]
+            .copy_from_slice(&objective.weights(self.max_slots));
+
+        // Push each gap's sensitivity down to the two gaps every placement creates.
+        for num_slots in (2..=self.max_slots).rev() {
+            for num_values in num_slots..=self.max_values {
+                let base = self.index(num_slots, num_values, 0);
+                if sensitivity[base + 1..base + num_slots + 1]
+                    .iter()
+                    .all(|&weight| weight == 0.0)
+                {
+                    continue;
+                }
+                for offset in 0..num_values {
+                    let placement = policy.placement(num_slots, num_values, offset);
+                    let right_slots = num_slots - 1 - placement;
+                    let right_values = num_values - 1 - offset;
+                    for left in 0..=placement.min(offset) {
+                        let left_fit = self.fit_probability(placement, offset, left);
+                        for right in 0..=right_slots.min(right_values) {
+                            let count = left + right + 1;
+                            let scale = sensitivity[base + count]
+                                * self.binom(offset, left)
+                                * self.binom(right_values, right)
+                                / (num_values as f64 * self.binom(num_values - 1, count - 1));
+                            if scale == 0.0 {
+                                continue;
+                            }
+                            let right_fit = self.fit_probability(right_slots, right_values, right);
+                            sensitivity[self.index(placement, offset, left)] += scale * right_fit;
+                            sensitivity[self.index(right_slots, right_values, right)] +=
+                                scale * left_fit;
+                        }
+                    }
+                }
+            }
+        }
+        sensitivity
+    }
+
+    /// The first-order effect on the objective of placing the number at `offset` in
+    /// `placement`, given the sensitivities of the gap's fit probabilities.
+    fn placement_gain(
+        &self,
+        sensitivity: &[f64],
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+        placement: usize,
+    ) -> f64 {
+        let base = self.index(num_slots, num_values, 0);
+        let mut gain = 0.0;
+        self.accumulate(num_slots, num_values, offset, placement, |count, weight| {
+            gain += sensitivity[base + count] * weight
+                / (num_values as f64 * self.binom(num_values - 1, count - 1));
+        });
+        gain
+    }
+}
+
+/// Searches for the gap-local policy with the highest value of `objective`, returning
+/// it with its exact placement-count probabilities. `on_iteration` is called with
+/// the value after each improvement.
+///
+/// Only the win probability splits into independent gaps, so the other objectives
+/// cannot be solved gap by gap. Instead this starts from the policy that maximises
+/// the win probability and repeatedly switches every placement whose switch improves
+/// the objective to first order, backing off to the most promising half of the
+/// switches until the exact value goes up. It stops when no switch helps to first
+/// order or when not even the most promising one raises the exact value, so the
+/// policy is a local optimum only to first order; the value reported for it is exact.
+///
+/// Placements that leave one side of a gap with more slots than values are never
+/// considered, so every gap the policy creates can still be filled.
+pub fn optimise(
+    objective: Objective,
+    max_slots: usize,
+    max_values: usize,
+    max_iterations: usize,
+    mut on_iteration: impl FnMut(usize, f64),
+) -> (Policy, PlacementSolver) {
+    let win = Solver::new(max_slots, max_values);
+    let mut policy = Policy::from_fn(max_slots, max_values, |num_slots, num_values, offset| {
+        win.best_placement(num_slots, num_values, offset).0
+    });
+    let mut solver = PlacementSolver::evaluate(&policy);
+    let mut value = solver.value(objective);
+    on_iteration(0, value);
+
+    for iteration in 1..=max_iterations {
+        let sensitivity = solver.sensitivities(objective, &policy);
+        let threshold = value.abs() * 1e-12;
+
+        // Every switch that looks better, as (index into the policy, placement, gain).
+        let mut switches: Vec<(usize, usize, f64)> = (2..=max_slots)
+            .into_par_iter()
+            .flat_map_iter(|num_slots| {
+                let mut switches = Vec::new();
+                for num_values in num_slots..=max_values {
+                    let start = policy.start(num_slots, num_values);
+                    for offset in 0..num_values {
+                        let current = policy.placement(num_slots, num_values, offset);
+                        let gain = |placement| {
+                            solver.placement_gain(
+                                &sensitivity,
+                                num_slots,
+                                num_values,
+                                offset,
+                                placement,
+                            )
+                        };
+                        let current_gain = gain(current);
+                        let first = (num_slots + offset).saturating_sub(num_values);
+                        let last = offset.min(num_slots - 1);
+                        let best = (first..=last)
+                            .map(|placement| (placement, gain(placement)))
+                            .fold((current, current_gain), |best, candidate| {
+                                if candidate.1 > best.1 {
+                                    candidate
+                                } else {
+                                    best
+                                }
+                            });
+                        if best.1 - current_gain > threshold {
+                            switches.push((start + offset, best.0, best.1 - current_gain));
+                        }
+                    }
+                }
+                switches
+            })
+            .collect();
+        if switches.is_empty() {
+            break;
+        }
+        switches.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        let mut improved = false;
+        let mut count = switches.len();
+        while count > 0 {
+            let mut candidate = policy.clone();
+            for &(index, placement, _) in &switches[..count] {
+                candidate.placements[index] = placement as u8;
+            }
+            let candidate_solver = PlacementSolver::evaluate(&candidate);
+            let candidate_value = candidate_solver.value(objective);
+            if candidate_value > value {
+                policy = candidate;
+                solver = candidate_solver;
+                value = candidate_value;
+                improved = true;
+                break;
+            }
+            count /= 2;
+        }
+        if !improved {
+            break;
+        }
+        on_iteration(iteration, value);
+    }
+
+    (policy, solver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Places `draws` one by one on an empty gap of `num_slots` slots, returning
+    /// whether every one of them fits.
+    fn fits(policy: &Policy, num_slots: usize, num_values: usize, draws: &[usize]) -> bool {
+        let mut board: Vec<Option<usize>> = vec![None; num_slots];
+        for &number in draws {
+            let left = board
+                .iter()
+                .rposition(|slot| slot.is_some_and(|value| value < number));
+            let right = board
+                .iter()
+                .position(|slot| slot.is_some_and(|value| value > number));
+            let first = left.map_or(0, |index| index + 1);
+            let last = right.unwrap_or(num_slots);
+            if first >= last {
+                return false;
+            }
+            let lower = left.map_or(0, |index| board[index].unwrap() + 1);
+            let upper = right.map_or(num_values, |index| board[index].unwrap());
+            let placement = policy.placement(last - first, upper - lower, number - lower);
+            board[first + placement] = Some(number);
+        }
+        true
+    }
+
+    /// The fraction of ordered draws of `count` distinct values that all fit.
+    fn brute_force_fit(policy: &Policy, num_slots: usize, num_values: usize, count: usize) -> f64 {
+        fn visit(
+            draws: &mut Vec<usize>,
+            count: usize,
+            num_values: usize,
+            check: &mut impl FnMut(&[usize]),
+        ) {
+            if draws.len() == count {
+                check(draws);
+                return;
+            }
+            for number in 0..num_values {
+                if !draws.contains(&number) {
+                    draws.push(number);
+                    visit(draws, count, num_values, check);
+                    draws.pop();
+                }
+            }
+        }
+
+        let (mut fitted, mut total) = (0usize, 0usize);
+        visit(&mut Vec::new(), count, num_values, &mut |draws| {
+            total += 1;
+            fitted += fits(policy, num_slots, num_values, draws) as usize;
+        });
+        fitted as f64 / total as f64
+    }
+
+    fn assert_matches_brute_force(policy: &Policy, max_slots: usize, max_values: usize) {
+        let solver = PlacementSolver::evaluate(policy);
+        for num_slots in 1..=max_slots {
+            for num_values in num_slots..=max_values {
+                for count in 0..=num_slots {
+                    let expected = brute_force_fit(policy, num_slots, num_values, count);
+                    let actual = solver.fit_probability(num_slots, num_values, count);
+                    assert!(
+                        (actual - expected).abs() < 1e-12,
+                        "{} slots, {} values, {} draws: {} != {}",
+                        num_slots,
+                        num_values,
+                        count,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fit_probability_matches_brute_force_for_the_win_optimal_policy() {
+        let win = Solver::new(4, 7);
+        let policy = Policy::from_fn(4, 7, |num_slots, num_values, offset| {
+            win.best_placement(num_slots, num_values, offset).0
+        });
+        assert_matches_brute_force(&policy, 4, 7);
+    }
+
+    #[test]
+    fn fit_probability_matches_brute_force_for_the_lowest_feasible_policy() {
+        let policy = Policy::from_fn(4, 7, |num_slots, num_values, offset| {
+            (num_slots + offset).saturating_sub(num_values)
+        });
+        assert_matches_brute_force(&policy, 4, 7);
+    }
+
+    #[test]
+    fn a_whole_game_is_won_as_often_as_the_solver_says() {
+        let win = Solver::new(4, 7);
+        let policy = Policy::from_fn(4, 7, |num_slots, num_values, offset| {
+            win.best_placement(num_slots, num_values, offset).0
+        });
+        let solver = PlacementSolver::evaluate(&policy);
+        assert!((solver.value(Objective::Win) - win.win_probability(4, 7)).abs() < 1e-12);
+        assert_eq!(solver.at_least(0), 1.0);
+        assert_eq!(solver.at_least(1), 1.0);
+    }
+
+    #[test]
+    fn optimise_never_lowers_its_objective() {
+        let mut values = Vec::new();
+        optimise(Objective::ExpectedPlacements, 5, 12, 20, |_, value| {
+            values.push(value)
+        });
+        assert!(values.windows(2).all(|pair| pair[1] > pair[0]));
+    }
+}
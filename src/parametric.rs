@@ -0,0 +1,285 @@
+use rand::Rng;
+
+use crate::engine::new_rng;
+use crate::solver::Solver;
+
+/// The number of trained weights of a `ParametricPolicy`.
+pub const NUM_WEIGHTS: usize = 3;
+
+/// A gap-local placement policy with `NUM_WEIGHTS` parameters.
+///
+/// With `x` the number's position in its gap as a fraction and `r = s - 1 - p` the
+/// slots left on the right, placement `p` of a gap with `s` slots scores
+///
+/// `p * logit(x) + w0 * ln C(s - 1, p) + shape(p) + shape(r)`,
+/// `shape(k) = w1 * ln(k + 1) + w2 / (k + 1)`,
+///
+/// and the feasible placement with the highest score is chosen. This is the form of
+/// the continuous-limit optimum (see `ContinuousSolver`), with the log win
+/// probabilities of the two new gaps replaced by a curve in their slot counts: they
+/// fall almost linearly in the slot count, and the linear part is the same for every
+/// placement since `p + r` is fixed. The turn and the rest of the board are left out,
+/// since the optimal decision only depends on the gap.
+#[derive(Debug, Clone)]
+pub struct ParametricPolicy {
+    weights: [f64; NUM_WEIGHTS],
+    /// `ln_binomials[n][k]` is the log of "n choose k".
+    ln_binomials: Vec<Vec<f64>>,
+    /// `shape[k]` is the shape term of a gap with `k` slots.
+    shape: Vec<f64>,
+}
+
+impl ParametricPolicy {
+    /// The weights `train-parametric` found for the full game.
+    pub const TRAINED: [f64; NUM_WEIGHTS] = [1.0533, 0.1524, -0.5161];
+
+    pub fn new(weights: [f64; NUM_WEIGHTS], max_slots: usize) -> Self {
+        let ln_binomials = (0..max_slots)
+            .map(|n| {
+                (0..=n)
+                    .map(|k| (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum())
+                    .collect()
+            })
+            .collect();
+        let shape = (0..max_slots)
+            .map(|slots| weights[1] * ((slots + 1) as f64).ln() + weights[2] / (slots + 1) as f64)
+            .collect();
+        Self {
+            weights,
+            ln_binomials,
+            shape,
+        }
+    }
+
+    pub fn weights(&self) -> [f64; NUM_WEIGHTS] {
+        self.weights
+    }
+
+    /// The placement (0-indexed within the gap) for the number at `offset`.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: usize) -> usize {
+        let logit = Self::logit(num_values, offset);
+        // Only consider placements that leave each side as many values as slots.
+        let lowest = (num_slots + offset).saturating_sub(num_values);
+        let highest = offset.min(num_slots - 1);
+        if lowest > highest {
+            return highest;
+        }
+        let mut best = (lowest, f64::NEG_INFINITY);
+        for placement in lowest..=highest {
+            let score = self.score(num_slots, logit, placement);
+            if score > best.1 {
+                best = (placement, score);
+            }
+        }
+        best.0
+    }
+
+    /// The exponential of each placement's score less the best score, so the chosen
+    /// placement scores 1, and 0 for the placements `placement` never considers.
+    pub fn placement_scores(&self, num_slots: usize, num_values: usize, offset: usize) -> Vec<f64> {
+        let best = self.placement(num_slots, num_values, offset);
+        let mut scores = vec![0.0; num_slots];
+        let lowest = (num_slots + offset).saturating_sub(num_values);
+        let highest = offset.min(num_slots - 1);
+        if lowest > highest {
+            scores[best] = 1.0;
+            return scores;
+        }
+        let logit = Self::logit(num_values, offset);
+        let best_score = self.score(num_slots, logit, best);
+        for (placement, score) in scores.iter_mut().enumerate().take(highest + 1).skip(lowest) {
+            *score = (self.score(num_slots, logit, placement) - best_score).exp();
+        }
+        scores
+    }
+
+    /// The log odds of the number's position in its gap.
+    fn logit(num_values: usize, offset: usize) -> f64 {
+        let fraction = (offset + 1) as f64 / (num_values + 1) as f64;
+        (fraction / (1.0 - fraction)).ln()
+    }
+
+    fn score(&self, num_slots: usize, logit: f64, placement: usize) -> f64 {
+        let right_slots = num_slots - 1 - placement;
+        placement as f64 * logit
+            + self.weights[0] * self.ln_binomials[num_slots - 1][placement]
+            + self.shape[placement]
+            + self.shape[right_slots]
+    }
+
+    /// The exact probability that following the policy wins a game with `max_slots`
+    /// slots and `max_values` values.
+    pub fn win_probability(&self, max_slots: usize, max_values: usize) -> f64 {
+        Solver::evaluate(max_slots, max_values, |num_slots, num_values, offset| {
+            self.placement(num_slots, num_values, offset)
+        })
+        .win_probability(max_slots, max_values)
+    }
+}
+
+/// Trains a policy's weights with evolution strategies, starting from `weights`.
+///
+/// Each iteration scores `pairs` mirrored random perturbations of size `sigma` by
+/// their exact log win probability, and moves the weights a distance `sigma` along
+/// the average perturbation weighted by how much better it scored than its mirror.
+/// The steps are noisy, so the best weights seen are returned rather than the last.
+/// The exact win probability stands in for simulated games: at a win rate near
+/// 0.013% telling two similar policies apart would take billions of games.
+pub fn train(
+    weights: [f64; NUM_WEIGHTS],
+    max_slots: usize,
+    max_values: usize,
+    iterations: usize,
+    pairs: usize,
+    sigma: f64,
+    mut on_iteration: impl FnMut(usize, &ParametricPolicy, f64),
+) -> ParametricPolicy {
+    let mut rng = new_rng();
+    let mut policy = ParametricPolicy::new(weights, max_slots);
+    let mut best = (
+        policy.clone(),
+        policy.win_probability(max_slots, max_values),
+    );
+    let fitness = |weights: [f64; NUM_WEIGHTS]| {
+        ParametricPolicy::new(weights, max_slots)
+            .win_probability(max_slots, max_values)
+            .ln()
+    };
+
+    for iteration in 1..=iterations {
+        let mut direction = [0.0; NUM_WEIGHTS];
+        for _ in 0..pairs {
+            let noise: [f64; NUM_WEIGHTS] = std::array::from_fn(|_| standard_normal(&mut rng));
+            let plus = fitness(std::array::from_fn(|i| {
+                policy.weights[i] + sigma * noise[i]
+            }));
+            let minus = fitness(std::array::from_fn(|i| {
+                policy.weights[i] - sigma * noise[i]
+            }));
+            // A perturbation that loses every game on both sides says nothing.
+            let difference = if plus.is_finite() || minus.is_finite() {
+                plus.max(-1e9) - minus.max(-1e9)
+            } else {
+                0.0
+            };
+            for i in 0..NUM_WEIGHTS {
+                direction[i] += difference * noise[i];
+            }
+        }
+
+        let length = direction.iter().map(|d| d * d).sum::<f64>().sqrt();
+        if length > 0.0 {
+            let weights =
+                std::array::from_fn(|i| policy.weights[i] + sigma * direction[i] / length);
+            policy = ParametricPolicy::new(weights, max_slots);
+        }
+        let win_probability = policy.win_probability(max_slots, max_values);
+        if win_probability > best.1 {
+            best = (policy.clone(), win_probability);
+        }
+        on_iteration(iteration, &policy, win_probability);
+    }
+    best.0
+}
+
+/// A standard normal sample by the Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+    let uniform: f64 = rng.random_range(f64::EPSILON..1.0);
+    let angle: f64 = rng.random_range(0.0..std::f64::consts::TAU);
+    (-2.0 * uniform.ln()).sqrt() * angle.cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::feasible_placements;
+
+    const WEIGHTS: [[f64; NUM_WEIGHTS]; 3] =
+        [ParametricPolicy::TRAINED, [0.0, 0.0, 0.0], [-2.0, 1.5, 3.0]];
+
+    /// The probability that `policy` fills the empty slots of `board`, with the numbers
+    /// marked in `drawn` already drawn, found by playing every draw order out.
+    fn play_every_order(
+        policy: &ParametricPolicy,
+        board: &mut [Option<usize>],
+        drawn: &mut [bool],
+    ) -> f64 {
+        if board.iter().all(Option::is_some) {
+            return 1.0;
+        }
+        let undrawn: Vec<usize> = (0..drawn.len()).filter(|&number| !drawn[number]).collect();
+        let mut total = 0.0;
+        for &number in &undrawn {
+            // The empty slots between the numbers either side of this one.
+            let lower = board
+                .iter()
+                .rposition(|&slot| slot.is_some_and(|value| value < number));
+            let upper = board
+                .iter()
+                .position(|&slot| slot.is_some_and(|value| value > number));
+            let first = lower.map_or(0, |slot| slot + 1);
+            let last = upper.unwrap_or(board.len());
+            if first == last {
+                continue;
+            }
+            let low_value = lower.map_or(0, |slot| board[slot].unwrap() + 1);
+            let high_value = upper.map_or(drawn.len(), |slot| board[slot].unwrap());
+            let placement =
+                policy.placement(last - first, high_value - low_value, number - low_value);
+            board[first + placement] = Some(number);
+            drawn[number] = true;
+            total += play_every_order(policy, board, drawn);
+            board[first + placement] = None;
+            drawn[number] = false;
+        }
+        total / undrawn.len() as f64
+    }
+
+    #[test]
+    fn places_only_where_the_gap_can_still_be_filled() {
+        for weights in WEIGHTS {
+            let policy = ParametricPolicy::new(weights, 8);
+            for num_slots in 1..=8 {
+                for num_values in num_slots..=30 {
+                    for offset in 0..num_values {
+                        let placement = policy.placement(num_slots, num_values, offset);
+                        assert!(
+                            feasible_placements(num_slots, num_values, offset).contains(&placement),
+                            "{:?} places {} of {} in slot {} of {}",
+                            weights,
+                            offset,
+                            num_values,
+                            placement,
+                            num_slots
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn win_probability_matches_playing_every_draw_order() {
+        for weights in WEIGHTS {
+            let policy = ParametricPolicy::new(weights, 4);
+            for (num_slots, num_values) in [(3, 6), (4, 7), (4, 9)] {
+                let expected = play_every_order(
+                    &policy,
+                    &mut vec![None; num_slots],
+                    &mut vec![false; num_values],
+                );
+                // Evaluated by `Solver::evaluate`, gap by gap.
+                let actual = policy.win_probability(num_slots, num_values);
+                assert!(
+                    (actual - expected).abs() < 1e-12,
+                    "{:?} on {} slots and {} values: {} against {}",
+                    weights,
+                    num_slots,
+                    num_values,
+                    actual,
+                    expected
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use crate::engine::{draw_numbers, forced_placement, play_game_observed, NUM_SLOTS};
+use crate::solver::Solver;
+use crate::strategy::Strategy;
+use rayon::prelude::*;
+
+/// The upper limits of the margin buckets of `RegretStats::by_margin`: how far below
+/// the best score the strategy's runner-up slot scored, as a fraction of the best.
+pub const MARGIN_LIMITS: [f64; 3] = [0.001, 0.01, 0.1];
+
+/// Regret summed over a group of decisions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegretBucket {
+    pub decisions: usize,
+    /// Decisions whose placement had a lower win probability than the best one.
+    pub suboptimal: usize,
+    pub total_regret: f64,
+}
+
+impl RegretBucket {
+    fn add(&mut self, regret: f64) {
+        self.decisions += 1;
+        if regret > 0.0 {
+            self.suboptimal += 1;
+        }
+        self.total_regret += regret;
+    }
+
+    pub fn merge(&mut self, other: &RegretBucket) {
+        self.decisions += other.decisions;
+        self.suboptimal += other.suboptimal;
+        self.total_regret += other.total_regret;
+    }
+
+    pub fn mean_regret(&self) -> f64 {
+        if self.decisions == 0 {
+            0.0
+        } else {
+            self.total_regret / self.decisions as f64
+        }
+    }
+}
+
+/// The regret of every decision a strategy made, grouped by gap size, by turn and by
+/// how close the strategy's own scores made the call.
+///
+/// The regret of a decision is the optimal win probability of the gap minus the win
+/// probability after the chosen placement: a difference of gap win probabilities,
+/// not a factor. Since the game is won exactly when every gap is, the decision
+/// multiplies the strategy's chance of winning by `chosen / best`, so it costs the
+/// fraction `regret / best` of it.
+#[derive(Debug, Clone)]
+pub struct RegretStats {
+    /// Indexed by the number of slots in the gap.
+    pub by_gap_slots: [RegretBucket; NUM_SLOTS + 1],
+    /// Indexed by the number of numbers already placed.
+    pub by_turn: [RegretBucket; NUM_SLOTS],
+    /// The decisions of a strategy that scores slots, indexed by the first limit in
+    /// `MARGIN_LIMITS` its margin falls below, or past the end, so near-ties come first.
+    pub by_margin: [RegretBucket; MARGIN_LIMITS.len() + 1],
+}
+
+impl Default for RegretStats {
+    fn default() -> Self {
+        Self {
+            by_gap_slots: [RegretBucket::default(); NUM_SLOTS + 1],
+            by_turn: [RegretBucket::default(); NUM_SLOTS],
+            by_margin: [RegretBucket::default(); MARGIN_LIMITS.len() + 1],
+        }
+    }
+}
+
+impl RegretStats {
+    fn merge(&mut self, other: &RegretStats) {
+        for (bucket, other) in self.by_gap_slots.iter_mut().zip(&other.by_gap_slots) {
+            bucket.merge(other);
+        }
+        for (bucket, other) in self.by_turn.iter_mut().zip(&other.by_turn) {
+            bucket.merge(other);
+        }
+        for (bucket, other) in self.by_margin.iter_mut().zip(&other.by_margin) {
+            bucket.merge(other);
+        }
+    }
+
+    /// Regret summed over every decision.
+    pub fn total(&self) -> RegretBucket {
+        let mut total = RegretBucket::default();
+        for bucket in &self.by_turn {
+            total.merge(bucket);
+        }
+        total
+    }
+}
+
+/// How far below the best score the runner-up scored, as a fraction of the best, or
+/// `None` if no slot scored above zero.
+fn runner_up_margin(scores: &[f64]) -> Option<f64> {
+    let mut best = 0.0;
+    let mut runner_up = 0.0;
+    for &score in scores {
+        if score > best {
+            runner_up = best;
+            best = score;
+        } else if score > runner_up {
+            runner_up = score;
+        }
+    }
+    (best > 0.0).then(|| (best - runner_up) / best)
+}
+
+/// Runs random games for multiple strategies and scores every decision against `solver`.
+///
+/// # Arguments
+///
+/// * `strategies` - A slice of tuples, where each tuple contains the strategy name and reference.
+/// * `solver` - The optimal win probabilities to score against.
+/// * `num_simulations` - The number of simulations to run.
+///
+/// # Returns
+///
+/// A vector of tuples, each containing the strategy name and its regret statistics.
+pub fn run_regret_multi(
+    strategies: &[(String, Arc<dyn Strategy>)],
+    solver: &Solver,
+    num_simulations: usize,
+) -> Vec<(String, RegretStats)> {
+    let num_strategies = strategies.len();
+
+    let stats = (0..num_simulations)
+        .into_par_iter()
+        .fold(
+            || vec![RegretStats::default(); num_strategies],
+            |mut acc, _| {
+                play_game_observed(strategies, &draw_numbers(), |decision| {
+                    let gap = &decision.gap;
+                    let num_slots = gap.last_index - gap.first_index + 1;
+                    if num_slots < 2 {
+                        return;
+                    }
+                    let num_values = (gap.upper - gap.lower - 1) as usize;
+                    let offset = (decision.number - gap.lower - 1) as usize;
+                    let placement = decision.slot - gap.first_index;
+
+                    let best = solver.best_placement(num_slots, num_values, offset).1;
+                    let chosen =
+                        solver.placement_win_probability(num_slots, num_values, offset, placement);
+                    // Ignore rounding differences between placements that tie exactly.
+                    let regret = if best - chosen > best * 1e-12 {
+                        best - chosen
+                    } else {
+                        0.0
+                    };
+
+                    let stats = &mut acc[decision.strategy_index];
+                    stats.by_gap_slots[num_slots].add(regret);
+                    stats.by_turn[decision.turn].add(regret);
+
+                    let strategy = strategies[decision.strategy_index].1.as_ref();
+                    if !strategy.want_full_control()
+                        && forced_placement(gap, decision.number).is_some()
+                    {
+                        return;
+                    }
+                    if let Some(margin) = strategy
+                        .slot_scores(
+                            gap.lower,
+                            gap.upper,
+                            gap.first_index,
+                            gap.last_index,
+                            decision.number,
+                            decision.board,
+                        )
+                        .and_then(|scores| runner_up_margin(&scores))
+                    {
+                        let bucket = MARGIN_LIMITS
+                            .iter()
+                            .position(|&limit| margin < limit)
+                            .unwrap_or(MARGIN_LIMITS.len());
+                        stats.by_margin[bucket].add(regret);
+                    }
+                });
+                acc
+            },
+        )
+        .reduce(
+            || vec![RegretStats::default(); num_strategies],
+            |mut acc, local| {
+                for (stats, local) in acc.iter_mut().zip(&local) {
+                    stats.merge(local);
+                }
+                acc
+            },
+        );
+
+    strategies
+        .iter()
+        .zip(stats)
+        .map(|((name, _), stats)| (name.clone(), stats))
+        .collect()
+}
@@ -0,0 +1,123 @@
+use std::io::{self, BufRead};
+
+use crate::engine::{LOWER_BOUND, NUM_SLOTS, UPPER_BOUND};
+
+/// Loads draw sequences from a text file.
+///
+/// Each non-empty line holds one game: the drawn numbers in order, separated by
+/// whitespace or commas. Lines starting with `#` are comments. A game lists at least
+/// one number and may list fewer than `NUM_SLOTS` (e.g. a hand-played game that
+/// stopped when it was lost). A file without any game is an error, since there would
+/// be nothing to average.
+pub fn load_sequences(file_path: &str) -> io::Result<Vec<Vec<i32>>> {
+    let file = std::fs::File::open(file_path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut sequences = Vec::new();
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", file_path, line_index + 1, message),
+            )
+        };
+
+        let mut numbers = Vec::new();
+        for token in line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+        {
+            let number: i32 = token
+                .parse()
+                .map_err(|_| invalid(format!("'{}' is not a number", token)))?;
+            if number <= LOWER_BOUND || number >= UPPER_BOUND {
+                return Err(invalid(format!(
+                    "{} is outside the range {}..{}",
+                    number,
+                    LOWER_BOUND + 1,
+                    UPPER_BOUND
+                )));
+            }
+            if numbers.contains(&number) {
+                return Err(invalid(format!("{} is drawn more than once", number)));
+            }
+            numbers.push(number);
+        }
+
+        if numbers.is_empty() {
+            return Err(invalid("no numbers drawn".to_string()));
+        }
+        if numbers.len() > NUM_SLOTS {
+            return Err(invalid(format!(
+                "{} numbers drawn but the board only has {} slots",
+                numbers.len(),
+                NUM_SLOTS
+            )));
+        }
+        sequences.push(numbers);
+    }
+
+    if sequences.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: no draw sequences", file_path),
+        ));
+    }
+    Ok(sequences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a file of its own in the temporary directory.
+    fn write_file(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!(
+            "twenty_number_challenge_{}_{}.txt",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn loads_games_separated_by_whitespace_or_commas() {
+        let path = write_file("games", "# two games\n1 2 3\n\n999,0, 500\n");
+        assert_eq!(
+            load_sequences(&path).unwrap(),
+            vec![vec![1, 2, 3], vec![999, 0, 500]]
+        );
+    }
+
+    #[test]
+    fn a_file_without_games_is_an_error() {
+        let path = write_file("empty", "# nothing here\n\n");
+        let err = load_sequences(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("no draw sequences"));
+    }
+
+    #[test]
+    fn rejects_numbers_the_game_cannot_draw() {
+        for (name, contents, message) in [
+            ("range", "1 1000\n", "outside the range"),
+            ("repeat", "5 6 5\n", "more than once"),
+            ("word", "5 six\n", "not a number"),
+            ("separators", ",,,\n", "no numbers drawn"),
+        ] {
+            let err = load_sequences(&write_file(name, contents)).unwrap_err();
+            assert!(err.to_string().contains(message), "{}", err);
+            assert!(err.to_string().contains(":1:"), "{}", err);
+        }
+        let too_many: Vec<String> = (0..=NUM_SLOTS).map(|number| number.to_string()).collect();
+        let err = load_sequences(&write_file("long", &too_many.join(" "))).unwrap_err();
+        assert!(err.to_string().contains("slots"), "{}", err);
+    }
+}
@@ -0,0 +1,349 @@
+use rayon::prelude::*;
+
+/// Pascal's triangle up to `max_n` rows and `max_k` columns:
+/// `binomials[n * (max_k + 1) + k]` is "n choose k".
+pub fn binomial_table(max_n: usize, max_k: usize) -> Vec<f64> {
+    let mut binomials = vec![0.0; (max_n + 1) * (max_k + 1)];
+    for n in 0..=max_n {
+        binomials[n * (max_k + 1)] = 1.0;
+        for k in 1..=max_k.min(n) {
+            binomials[n * (max_k + 1) + k] =
+                binomials[(n - 1) * (max_k + 1) + k - 1] + binomials[(n - 1) * (max_k + 1) + k];
+        }
+    }
+    binomials
+}
+
+/// The probability that, of the other `num_slots - 1` numbers a gap with `num_values`
+/// values receives, exactly `placement` fall below the number at `offset`: the
+/// chance that placing it in slot `placement` leaves room on both sides.
+pub fn split_probability(
+    num_slots: usize,
+    num_values: usize,
+    offset: usize,
+    placement: usize,
+) -> f64 {
+    let binom = |n: usize, k: usize| {
+        if k > n {
+            return 0.0;
+        }
+        (1..=k).fold(1.0, |product, i| product * (n - k + i) as f64 / i as f64)
+    };
+    let right_values = num_values - 1 - offset;
+    let right_slots = num_slots - 1 - placement;
+    binom(offset, placement) * binom(right_values, right_slots)
+        / binom(num_values - 1, num_slots - 1)
+}
+
+/// Exact win probabilities of the optimal policy for the gap-local game.
+///
+/// A gap with `num_slots` empty slots and `num_values` possible numbers is won when
+/// the numbers that land in it can all be placed. The game is won exactly when every
+/// gap is, and a gap only matters when it receives as many numbers as it has slots,
+/// so each gap can be solved on its own: placing the number at `offset` in
+/// `placement` wins with the probability that the other numbers split between the
+/// two new gaps to fit, times the probability of winning both of them.
+pub struct Solver {
+    max_slots: usize,
+    max_values: usize,
+    /// `binomials[n * (max_slots + 1) + k]` is "n choose k".
+    binomials: Vec<f64>,
+    /// `win[num_slots * (max_values + 1) + num_values]` is the optimal win probability.
+    win: Vec<f64>,
+}
+
+impl Solver {
+    /// Solves every gap with up to `max_slots` slots and `max_values` values.
+    pub fn new(max_slots: usize, max_values: usize) -> Self {
+        Self::build(
+            max_slots,
+            max_values,
+            |solver, num_slots, num_values, offset| {
+                solver.best_placement(num_slots, num_values, offset).1
+            },
+        )
+    }
+
+    /// Computes the win probabilities of a fixed policy instead of the optimal one.
+    ///
+    /// `policy` returns the placement (0-indexed within the gap) for a gap with
+    /// `num_slots` slots and `num_values` values and the number at `offset`.
+    pub fn evaluate(
+        max_slots: usize,
+        max_values: usize,
+        policy: impl Fn(usize, usize, usize) -> usize + Sync,
+    ) -> Self {
+        Self::build(
+            max_slots,
+            max_values,
+            |solver, num_slots, num_values, offset| {
+                let placement = policy(num_slots, num_values, offset);
+                solver.placement_win_probability(num_slots, num_values, offset, placement)
+            },
+        )
+    }
+
+    /// Fills the win table, using `offset_win` for the win probability of each offset
+    /// of a gap once every gap with fewer slots is known.
+    fn build(
+        max_slots: usize,
+        max_values: usize,
+        offset_win: impl Fn(&Solver, usize, usize, usize) -> f64 + Sync,
+    ) -> Self {
+        let mut solver = Self {
+            max_slots,
+            max_values,
+            binomials: binomial_table(max_values, max_slots),
+            win: vec![0.0; (max_slots + 1) * (max_values + 1)],
+        };
+
+        // Empty gaps are always won, as are single slots with at least one value.
+        for num_values in 0..=max_values {
+            solver.win[num_values] = 1.0;
+        }
+        if max_slots >= 1 {
+            for num_values in 1..=max_values {
+                solver.win[(max_values + 1) + num_values] = 1.0;
+            }
+        }
+
+        // Each gap only depends on gaps with fewer slots, so a whole row can be solved at once.
+        for num_slots in 2..=max_slots {
+            let row: Vec<f64> = (0..=max_values)
+                .into_par_iter()
+                .map(|num_values| {
+                    if num_values < num_slots {
+                        return 0.0;
+                    }
+                    let total: f64 = (0..num_values)
+                        .map(|offset| offset_win(&solver, num_slots, num_values, offset))
+                        .sum();
+                    total / num_values as f64
+                })
+                .collect();
+            let start = num_slots * (max_values + 1);
+            solver.win[start..start + max_values + 1].copy_from_slice(&row);
+        }
+
+        solver
+    }
+
+    fn binom(&self, n: usize, k: usize) -> f64 {
+        if k > n {
+            0.0
+        } else {
+            self.binomials[n * (self.max_slots + 1) + k]
+        }
+    }
+
+    /// The probability of winning a gap when every placement is made optimally.
+    pub fn win_probability(&self, num_slots: usize, num_values: usize) -> f64 {
+        if num_slots > num_values {
+            return 0.0;
+        }
+        self.win[num_slots * (self.max_values + 1) + num_values]
+    }
+
+    /// The probability of winning a gap after placing the number at `offset`
+    /// (0-indexed among the gap's values) in slot `placement` of the gap.
+    pub fn placement_win_probability(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+        placement: usize,
+    ) -> f64 {
+        let left_values = offset;
+        let right_values = num_values - 1 - offset;
+        let right_slots = num_slots - 1 - placement;
+
+        // Hypergeometric probability that exactly `placement` of the remaining
+        // numbers land to the left of this one.
+        let split = self.binom(left_values, placement) * self.binom(right_values, right_slots)
+            / self.binom(num_values - 1, num_slots - 1);
+        if split == 0.0 {
+            return 0.0;
+        }
+
+        split
+            * self.win_probability(placement, left_values)
+            * self.win_probability(right_slots, right_values)
+    }
+
+    /// The placement with the highest win probability, preferring the lowest slot on ties.
+    pub fn best_placement(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+    ) -> (usize, f64) {
+        let mut best = (0, -1.0);
+        for placement in 0..num_slots {
+            let probability =
+                self.placement_win_probability(num_slots, num_values, offset, placement);
+            if probability > best.1 {
+                best = (placement, probability);
+            }
+        }
+        best
+    }
+
+    /// How much the win probability of the whole game changes per unit change in the
+    /// win probability of each gap, when every placement is made by `policy`.
+    ///
+    /// `sensitivity[num_slots * (max_values + 1) + num_values]` is the derivative for
+    /// that gap shape, so changing one decision of the policy changes the win rate by
+    /// roughly its sensitivity times the change in the gap's win probability.
+    pub fn sensitivities(&self, policy: impl Fn(usize, usize, usize) -> usize) -> Vec<f64> {
+        let stride = self.max_values + 1;
+        let mut sensitivity = vec![0.0; (self.max_slots + 1) * stride];
+        sensitivity[self.max_slots * stride + self.max_values] = 1.0;
+
+        // Push each gap's sensitivity down to the two gaps every placement creates.
+        for num_slots in (2..=self.max_slots).rev() {
+            for num_values in num_slots..=self.max_values {
+                let weight = sensitivity[num_slots * stride + num_values];
+                if weight == 0.0 {
+                    continue;
+                }
+                for offset in 0..num_values {
+                    let placement = policy(num_slots, num_values, offset);
+                    let left_values = offset;
+                    let right_values = num_values - 1 - offset;
+                    let right_slots = num_slots - 1 - placement;
+                    let split = self.binom(left_values, placement)
+                        * self.binom(right_values, right_slots)
+                        / self.binom(num_values - 1, num_slots - 1);
+                    if split == 0.0 {
+                        continue;
+                    }
+
+                    let scale = weight * split / num_values as f64;
+                    sensitivity[placement * stride + left_values] +=
+                        scale * self.win_probability(right_slots, right_values);
+                    sensitivity[right_slots * stride + right_values] +=
+                        scale * self.win_probability(placement, left_values);
+                }
+            }
+        }
+        sensitivity
+    }
+}
+
+/// The optimal policy of `Solver`, solved only as far as it is asked for.
+///
+/// `Solver` fills every gap up to a fixed size at once; this extends each row of
+/// gaps with the same slot count on demand instead, so games of any size can be
+/// played without knowing their number range beforehand. Solving a gap also solves
+/// every smaller gap its placements depend on, and keeps the best placement of each
+/// offset, which follows the same arithmetic as `Solver::best_placement`.
+pub struct OnDemandSolver {
+    max_slots: usize,
+    max_values: usize,
+    /// `binomials[n * (max_slots + 1) + k]` is "n choose k" for `n <= max_values`.
+    binomials: Vec<f64>,
+    /// `win[num_slots][num_values]` is known for every `num_values` below the row's length.
+    win: Vec<Vec<f64>>,
+    /// `placements[num_slots][num_values][offset]` is the best placement.
+    placements: Vec<Vec<Vec<u8>>>,
+}
+
+impl OnDemandSolver {
+    /// Starts with nothing solved for gaps of up to `max_slots` slots.
+    pub fn new(max_slots: usize) -> Self {
+        Self {
+            max_slots,
+            max_values: 0,
+            binomials: binomial_table(0, max_slots),
+            win: vec![Vec::new(); max_slots + 1],
+            placements: vec![Vec::new(); max_slots + 1],
+        }
+    }
+
+    /// How many slot counts have any gaps solved, starting from zero.
+    pub fn solved_slots(&self) -> usize {
+        self.win.iter().take_while(|row| !row.is_empty()).count()
+    }
+
+    /// How many value counts of gaps with `num_slots` slots are solved, starting from zero.
+    pub fn solved_values(&self, num_slots: usize) -> usize {
+        self.win[num_slots].len()
+    }
+
+    /// Solves the gap and every gap with fewer slots and values, if not done already.
+    ///
+    /// This runs on the calling thread only: callers hold a lock around it, and a rayon
+    /// worker waiting for its own jobs could otherwise pick up a game that needs the
+    /// same lock.
+    pub fn solve(&mut self, num_slots: usize, num_values: usize) {
+        if num_values < self.solved_values(num_slots) {
+            return;
+        }
+        if num_values > self.max_values {
+            self.max_values = num_values;
+            self.binomials = binomial_table(num_values, self.max_slots);
+        }
+
+        for row in 0..=num_slots {
+            for values in self.solved_values(row)..=num_values {
+                let (win, placements) = if row == 0 {
+                    (1.0, Vec::new())
+                } else if values < row {
+                    (0.0, Vec::new())
+                } else {
+                    let best: Vec<(usize, f64)> = (0..values)
+                        .map(|offset| self.best_placement(row, values, offset))
+                        .collect();
+                    let win = if row == 1 {
+                        1.0
+                    } else {
+                        best.iter().map(|&(_, win)| win).sum::<f64>() / values as f64
+                    };
+                    let placements = best.iter().map(|&(placement, _)| placement as u8).collect();
+                    (win, placements)
+                };
+                self.win[row].push(win);
+                self.placements[row].push(placements);
+            }
+        }
+    }
+
+    /// The optimal win probability of a gap, if it has been solved.
+    pub fn win_probability(&self, num_slots: usize, num_values: usize) -> Option<f64> {
+        self.win[num_slots].get(num_values).copied()
+    }
+
+    /// The best placement for the number at `offset` of a gap, if it has been solved.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: usize) -> Option<usize> {
+        let placements = self.placements[num_slots].get(num_values)?;
+        placements.get(offset).map(|&placement| placement as usize)
+    }
+
+    fn binom(&self, n: usize, k: usize) -> f64 {
+        if k > n {
+            0.0
+        } else {
+            self.binomials[n * (self.max_slots + 1) + k]
+        }
+    }
+
+    /// Like `Solver::best_placement`, for a gap whose smaller gaps are all solved.
+    fn best_placement(&self, num_slots: usize, num_values: usize, offset: usize) -> (usize, f64) {
+        let right_values = num_values - 1 - offset;
+        let mut best = (0, -1.0);
+        for placement in 0..num_slots {
+            let right_slots = num_slots - 1 - placement;
+            let split = self.binom(offset, placement) * self.binom(right_values, right_slots)
+                / self.binom(num_values - 1, num_slots - 1);
+            let probability = if split == 0.0 {
+                0.0
+            } else {
+                split * self.win[placement][offset] * self.win[right_slots][right_values]
+            };
+            if probability > best.1 {
+                best = (placement, probability);
+            }
+        }
+        best
+    }
+}
@@ -1,9 +1,20 @@
-use std::collections::HashMap;
-use std::hash::Hash;
-use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 
+use crate::continuous::ContinuousSolver;
+use crate::engine::{
+    choose_placement, draw_more_numbers, feasible_placements, forced_placement, game_seed,
+    play_out, Gap, LOWER_BOUND, UPPER_BOUND,
+};
+use crate::mcts;
+use crate::objective::{Objective, PlacementSolver, Policy};
+use crate::parametric::{ParametricPolicy, NUM_WEIGHTS};
+use crate::solver::{split_probability, OnDemandSolver};
 use crate::strategy::Strategy;
+use crate::table::{LookupKey, LookupTable, TableError};
 use libm::erf;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
 
 // Baseline validation strategies: FirstAvailable, LastAvailable, Middle
 pub struct FirstAvailableStrategy;
@@ -98,11 +109,11 @@ impl Strategy for OptimalWinStrategy {
 }
 
 pub struct CautiousOptimalStrategy {
-    epsilon_percent: usize,
+    epsilon_percent: f64,
 }
 
 impl CautiousOptimalStrategy {
-    pub fn new(epsilon_percent: usize) -> Self {
+    pub fn new(epsilon_percent: f64) -> Self {
         Self { epsilon_percent }
     }
 }
@@ -128,9 +139,8 @@ impl Strategy for CautiousOptimalStrategy {
         // Calculate slot size (range each slot would naturally cover).
         let slot_size = (upper - lower) as f64 / num_slots as f64;
 
-        // Convert EPSILON_PERCENT into a fraction (e.g., 5% becomes 0.05).
-        const SCALE: f64 = 100.0; // Fixed-point scale factor.
-        let epsilon_fraction = self.epsilon_percent as f64 / SCALE;
+        // Convert the percentage into a fraction (e.g., 5% becomes 0.05).
+        let epsilon_fraction = self.epsilon_percent / 100.0;
 
         // Determine epsilon threshold based on slot size.
         let epsilon_threshold = epsilon_fraction * slot_size;
@@ -151,9 +161,18 @@ impl Strategy for CautiousOptimalStrategy {
 }
 
 /// A strategy that uses a Gaussian distribution to bias slot selection.
-pub struct GaussianStrategy<const SIGMA_FIXED: usize>;
+pub struct GaussianStrategy {
+    /// The standard deviation, as a fraction of the gap.
+    sigma: f64,
+}
+
+impl GaussianStrategy {
+    pub fn new(sigma: f64) -> Self {
+        Self { sigma }
+    }
+}
 
-impl<const SIGMA_FIXED: usize> Strategy for GaussianStrategy<SIGMA_FIXED> {
+impl Strategy for GaussianStrategy {
     fn choose_slot(
         &self,
         lower: i32,
@@ -167,9 +186,7 @@ impl<const SIGMA_FIXED: usize> Strategy for GaussianStrategy<SIGMA_FIXED> {
         // We assume there are at least 3 slots available.
         assert!(num_slots >= 3, "GaussianStrategy requires at least 3 slots");
 
-        // Convert SIGMA_FIXED (fixed-point) to a floating-point sigma.
-        const SCALE: f64 = 1000.0;
-        let sigma = SIGMA_FIXED as f64 / SCALE;
+        let sigma = self.sigma;
 
         // Normalize the number to a fraction between 0 and 1.
         let fraction = (number - lower) as f64 / (upper - lower) as f64;
@@ -199,6 +216,12 @@ impl BinomialStrategy {
         }
         result
     }
+
+    /// The probability that `k` of the other `remaining` numbers land below the number
+    /// at fraction `x` of the gap.
+    fn likelihood(x: f64, remaining: usize, k: usize) -> f64 {
+        Self::binom(remaining, k) * x.powi(k as i32) * (1.0 - x).powi((remaining - k) as i32)
+    }
 }
 
 impl Strategy for BinomialStrategy {
@@ -227,9 +250,7 @@ impl Strategy for BinomialStrategy {
 
         // Evaluate each possible slot position (0 = leftmost, num_slots-1 = rightmost).
         for k in 0..num_slots {
-            let prob = Self::binom(remaining, k)
-                * x.powi(k as i32)
-                * (1.0 - x).powi((remaining - k) as i32);
+            let prob = Self::likelihood(x, remaining, k);
             if prob > best_prob {
                 best_prob = prob;
                 best_k = k;
@@ -239,6 +260,24 @@ impl Strategy for BinomialStrategy {
         // Return the actual slot index.
         first_slot + best_k
     }
+
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let num_slots = last_slot - first_slot + 1;
+        let x = (number - lower) as f64 / (upper - lower) as f64;
+        Some(
+            (0..num_slots)
+                .map(|k| Self::likelihood(x, num_slots - 1, k))
+                .collect(),
+        )
+    }
 }
 
 pub struct BinomialQuantizedStrategy;
@@ -255,6 +294,18 @@ impl BinomialQuantizedStrategy {
         }
         result
     }
+
+    /// How many ways the other `remaining` numbers can fall with `k` of them below
+    /// the number, which is proportional to the likelihood that it is the (k+1)th
+    /// smallest among all numbers destined for the gap.
+    fn likelihood(
+        num_options_lower: usize,
+        num_options_upper: usize,
+        remaining: usize,
+        k: usize,
+    ) -> f64 {
+        Self::binom(num_options_lower, k) * Self::binom(num_options_upper, remaining - k)
+    }
 }
 
 impl Strategy for BinomialQuantizedStrategy {
@@ -292,8 +343,7 @@ impl Strategy for BinomialQuantizedStrategy {
         for k in 0..num_slots {
             // Likelihood is proportional to:
             // binom(num_options_lower, k) * binom(num_options_upper, remaining - k)
-            let likelihood =
-                Self::binom(num_options_lower, k) * Self::binom(num_options_upper, remaining - k);
+            let likelihood = Self::likelihood(num_options_lower, num_options_upper, remaining, k);
             if likelihood > best_likelihood {
                 best_likelihood = likelihood;
                 best_k = k;
@@ -303,56 +353,71 @@ impl Strategy for BinomialQuantizedStrategy {
         // Return the absolute slot index.
         first_slot + best_k
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct LookupKey {
-    num_slots: usize,
-    num_values: usize,
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let num_slots = last_slot - first_slot + 1;
+        let num_options_lower = (number - lower) as usize - 1;
+        let num_options_upper = (upper - number) as usize - 1;
+        Some(
+            (0..num_slots)
+                .map(|k| Self::likelihood(num_options_lower, num_options_upper, num_slots - 1, k))
+                .collect(),
+        )
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Candidate {
-    upper_bound: i32,
-    placement_index: usize,
+/// Plays a lookup table, asking the fallbacks in order for any gap shape the table
+/// does not cover.
+pub struct LookupTableStrategy {
+    table: LookupTable,
+    fallbacks: Vec<Fallback>,
 }
 
-pub struct LookupTableStrategy {
-    // Map from (num_slots, num_values) to a sorted vector of candidate entries.
-    table: HashMap<LookupKey, Vec<Candidate>>,
+struct Fallback {
+    name: String,
+    strategy: Arc<dyn Strategy>,
+    /// How many placements the fallback has made.
+    uses: AtomicU64,
 }
 
 impl LookupTableStrategy {
-    pub fn new(file_path: &str) -> Self {
-        let mut raw_entries = Vec::new();
-        let file = std::fs::File::open(file_path).unwrap();
-        let reader = std::io::BufReader::new(file);
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            let num_slots = parts[0].parse().unwrap();
-            let num_values = parts[1].parse().unwrap();
-            let placement_index = parts[2].parse().unwrap();
-            let upper_bound: i32 = parts[3].parse().unwrap();
-            raw_entries.push((num_slots, num_values, placement_index, upper_bound));
-        }
-        // Build the map.
-        let mut table: HashMap<LookupKey, Vec<Candidate>> = HashMap::new();
-        for (num_slots, num_values, placement_index, upper_bound) in raw_entries {
-            let key = LookupKey {
-                num_slots,
-                num_values,
-            };
-            table.entry(key).or_default().push(Candidate {
-                upper_bound,
-                placement_index,
-            });
-        }
-        // Sort each vector by upper_bound.
-        for vec in table.values_mut() {
-            vec.sort_by_key(|candidate| candidate.upper_bound);
-        }
-        Self { table }
+    /// Loads a table, refusing one built for a different game than the engine plays.
+    pub fn new(file_path: &str) -> Result<Self, TableError> {
+        let table = LookupTable::load(file_path)?;
+        table.check_config(file_path)?;
+        Ok(Self {
+            table,
+            fallbacks: Vec::new(),
+        })
+    }
+
+    /// Uses a table bundled with the repository (see `LookupTable::load_bundled`).
+    pub fn bundled(file_name: &str) -> Result<Self, TableError> {
+        let table = LookupTable::load_bundled(file_name)?;
+        table.check_config(file_name)?;
+        Ok(Self {
+            table,
+            fallbacks: Vec::new(),
+        })
+    }
+
+    /// Adds a strategy to ask when the table and every earlier fallback lack a gap shape.
+    /// Its placements go through the engine's forced-move shortcuts like any strategy's.
+    pub fn with_fallback(mut self, name: &str, strategy: Arc<dyn Strategy>) -> Self {
+        self.fallbacks.push(Fallback {
+            name: name.to_string(),
+            strategy,
+            uses: AtomicU64::new(0),
+        });
+        self
     }
 }
 
@@ -362,41 +427,1130 @@ impl Strategy for LookupTableStrategy {
     }
 
     fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    // Takes the generator only to pass it on to a randomized fallback.
+    fn choose_slot_with_rng(
         &self,
         lower: i32, // gap lower boundary (exclusive)
         upper: i32, // gap upper boundary (exclusive)
         first_slot: usize,
         last_slot: usize,
         number: i32,
-        _current_board: &[Option<i32>],
+        current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
     ) -> usize {
         let gap_slots = last_slot - first_slot + 1;
         let gap_values = (upper - lower - 1) as usize; // because lower and upper are exclusive.
         let offset = number - lower - 1; // 0-indexed offset in the gap.
 
-        let key = LookupKey {
-            num_slots: gap_slots,
-            num_values: gap_values,
+        if let Some(placement_index) = self.table.placement(gap_slots, gap_values, offset) {
+            // Return the absolute slot index.
+            return first_slot + placement_index;
+        }
+
+        let fallback = self
+            .fallbacks
+            .iter()
+            .find(|fallback| fallback.strategy.covers_gap(gap_slots, gap_values))
+            .unwrap_or_else(|| {
+                panic!(
+                    "No lookup table entry or fallback for gap with {} slots and {} values",
+                    gap_slots, gap_values
+                )
+            });
+        fallback.uses.fetch_add(1, Ordering::Relaxed);
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
         };
+        choose_placement(fallback.strategy.as_ref(), &gap, number, current_board, rng)
+    }
 
-        // Get the block of candidates for these parameters.
-        let candidates = self.table.get(&key).unwrap_or_else(|| {
-            panic!(
-                "No lookup table entry for gap with {} slots and {} values",
-                gap_slots, gap_values
-            )
+    /// The win probability after each placement, following the table afterwards, if
+    /// the table records win probabilities; a fallback's scores for gaps it lacks.
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let gap_slots = last_slot - first_slot + 1;
+        let gap_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        if self
+            .table
+            .placement(gap_slots, gap_values, offset as i32)
+            .is_some()
+        {
+            return (0..gap_slots)
+                .map(|placement| {
+                    self.table
+                        .placement_win_probability(gap_slots, gap_values, offset, placement)
+                })
+                .collect();
+        }
+        self.fallbacks
+            .iter()
+            .find(|fallback| fallback.strategy.covers_gap(gap_slots, gap_values))?
+            .strategy
+            .slot_scores(lower, upper, first_slot, last_slot, number, current_board)
+    }
+
+    fn covers_gap(&self, num_slots: usize, num_values: usize) -> bool {
+        self.table.placement(num_slots, num_values, 0).is_some()
+            || self
+                .fallbacks
+                .iter()
+                .any(|fallback| fallback.strategy.covers_gap(num_slots, num_values))
+    }
+
+    fn stats(&self) -> Vec<(String, u64)> {
+        self.fallbacks
+            .iter()
+            .map(|fallback| {
+                (
+                    format!("{} fallback", fallback.name),
+                    fallback.uses.load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Places numbers by the continuous-limit thresholds of `ContinuousSolver`, so one
+/// policy covers any number range. A number's fraction of its gap is its distance
+/// from the lower bound over the gap's width.
+pub struct ThresholdStrategy {
+    solver: ContinuousSolver,
+}
+
+impl ThresholdStrategy {
+    pub fn new(max_slots: usize) -> Self {
+        Self {
+            solver: ContinuousSolver::new(max_slots),
+        }
+    }
+
+    /// The placement (0-indexed within the gap) for the number at `offset`, limited to
+    /// the placements that leave each side with at least as many values as slots.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: usize) -> usize {
+        let fraction = (offset + 1) as f64 / (num_values + 1) as f64;
+        let placement = self.solver.placement(num_slots, fraction);
+        placement.clamp(
+            (num_slots + offset).saturating_sub(num_values),
+            offset.min(num_slots - 1),
+        )
+    }
+}
+
+impl Strategy for ThresholdStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        first_slot + self.placement(num_slots, num_values, offset)
+    }
+}
+
+/// Plays the optimal policy without a precomputed table, solving each gap shape the
+/// first time a game reaches it. The solved gaps are shared by every thread playing
+/// with the same strategy, so after the first few games it is as fast as a table.
+pub struct SolverStrategy {
+    memo: RwLock<OnDemandSolver>,
+}
+
+impl SolverStrategy {
+    pub fn new(max_slots: usize) -> Self {
+        Self {
+            memo: RwLock::new(OnDemandSolver::new(max_slots)),
+        }
+    }
+
+    /// The best placement (0-indexed within the gap) for the number at `offset`.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: usize) -> usize {
+        if let Some(placement) = self
+            .memo
+            .read()
+            .unwrap()
+            .placement(num_slots, num_values, offset)
+        {
+            return placement;
+        }
+        let mut memo = self.memo.write().unwrap();
+        memo.solve(num_slots, num_values);
+        memo.placement(num_slots, num_values, offset).unwrap()
+    }
+
+    /// The optimal win probability of a gap, solving it first if needed.
+    pub fn win_probability(&self, num_slots: usize, num_values: usize) -> f64 {
+        if let Some(win) = self
+            .memo
+            .read()
+            .unwrap()
+            .win_probability(num_slots, num_values)
+        {
+            return win;
+        }
+        let mut memo = self.memo.write().unwrap();
+        memo.solve(num_slots, num_values);
+        memo.win_probability(num_slots, num_values).unwrap()
+    }
+
+    /// The win probability of the gap after each placement of the number at `offset`,
+    /// solving it first if needed.
+    pub fn placement_win_probabilities(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+    ) -> Vec<f64> {
+        // Solving the gap solves every smaller gap a placement leaves.
+        self.win_probability(num_slots, num_values);
+        let memo = self.memo.read().unwrap();
+        let right_values = num_values - 1 - offset;
+        (0..num_slots)
+            .map(|placement| {
+                let right_slots = num_slots - 1 - placement;
+                let split = split_probability(num_slots, num_values, offset, placement);
+                if split == 0.0 {
+                    0.0
+                } else {
+                    split
+                        * memo.win_probability(placement, offset).unwrap()
+                        * memo.win_probability(right_slots, right_values).unwrap()
+                }
+            })
+            .collect()
+    }
+
+    /// A lookup table of every gap shape solved so far, with its win probability.
+    pub fn to_table(&self) -> LookupTable {
+        let memo = self.memo.read().unwrap();
+        let keys: Vec<LookupKey> = (1..memo.solved_slots())
+            .flat_map(|num_slots| {
+                (num_slots..memo.solved_values(num_slots)).map(move |num_values| LookupKey {
+                    num_slots,
+                    num_values,
+                })
+            })
+            .collect();
+        let mut table = LookupTable::from_shapes(keys, |key, offset| {
+            memo.placement(key.num_slots, key.num_values, offset)
+                .unwrap()
         });
+        table.set_win_probabilities(|key| {
+            memo.win_probability(key.num_slots, key.num_values)
+                .unwrap_or(f64::NAN)
+        });
+        table
+    }
+}
+
+impl Strategy for SolverStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
 
-        // Find the best candidate that fits the offset.
-        let mut best_candidate_index = 0usize;
-        for (i, candidate) in candidates.iter().enumerate() {
-            best_candidate_index = i;
-            if candidate.upper_bound >= offset {
-                break;
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        first_slot + self.placement(num_slots, num_values, offset)
+    }
+
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        Some(self.placement_win_probabilities(num_slots, num_values, offset))
+    }
+}
+
+/// Chooses the placement that maximises the expected number of numbers still to be
+/// placed on the whole board, if the rest of the game follows a gap-local `policy`.
+///
+/// Unlike the win probability, the expected placements do not split into gaps: the
+/// game ends as soon as any gap runs out of room, so how long one gap can keep going
+/// is worth more when the others are roomy. With `n` undrawn values, the next `k`
+/// draws all fit exactly when every gap can fit the `k_i` of them it receives, so
+///
+/// `P(at least k more placed) = sum over splits of product C(v_i, k_i) * fit_i(k_i) / C(n, k)`,
+///
+/// a coefficient of the product of one polynomial per gap. Each candidate is scored
+/// by the sum of these probabilities over `k`, and the policy's own placement is kept
+/// unless another scores higher; how often that happens is counted.
+pub struct BoardAwareStrategy {
+    policy: Policy,
+    placements: PlacementSolver,
+    disagreements: AtomicU64,
+}
+
+impl BoardAwareStrategy {
+    pub fn new(policy: Policy) -> Self {
+        let placements = PlacementSolver::evaluate(&policy);
+        Self {
+            policy,
+            placements,
+            disagreements: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of ways to draw each count of numbers from a gap so they all fit.
+    fn gap_polynomial(&self, num_slots: usize, num_values: usize) -> Vec<f64> {
+        (0..=num_slots)
+            .map(|count| {
+                BinomialStrategy::binom(num_values, count)
+                    * self
+                        .placements
+                        .fit_probability(num_slots, num_values, count)
+            })
+            .collect()
+    }
+
+    fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+        let mut product = vec![0.0; a.len() + b.len() - 1];
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                product[i + j] += x * y;
             }
         }
+        product
+    }
 
-        // Return the absolute slot index.
-        first_slot + candidates[best_candidate_index].placement_index
+    /// The expected number of further placements, given the product of the gap
+    /// polynomials of a board with `num_values` undrawn values.
+    fn expected_placements(polynomial: &[f64], num_values: usize) -> f64 {
+        polynomial
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(count, ways)| ways / BinomialStrategy::binom(num_values, count))
+            .sum()
+    }
+}
+
+impl Strategy for BoardAwareStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        if num_values < num_slots {
+            // The gap can no longer be filled, and the policy has no placement for it.
+            return first_slot + offset.min(num_slots - 1);
+        }
+
+        // Every gap but the number's own.
+        let mut others = vec![1.0];
+        let mut other_values = 0;
+        let mut gap_lower = LOWER_BOUND;
+        let mut gap_slots = 0;
+        for slot in current_board.iter().copied().chain([Some(UPPER_BOUND)]) {
+            match slot {
+                None => gap_slots += 1,
+                Some(gap_upper) => {
+                    if gap_lower != lower {
+                        let gap_values = (gap_upper - gap_lower - 1).max(0) as usize;
+                        others =
+                            Self::multiply(&others, &self.gap_polynomial(gap_slots, gap_values));
+                        other_values += gap_values;
+                    }
+                    gap_lower = gap_upper;
+                    gap_slots = 0;
+                }
+            }
+        }
+
+        let score = |placement: usize| {
+            let right_slots = num_slots - 1 - placement;
+            let right_values = num_values - 1 - offset;
+            let polynomial = Self::multiply(
+                &Self::multiply(&others, &self.gap_polynomial(placement, offset)),
+                &self.gap_polynomial(right_slots, right_values),
+            );
+            Self::expected_placements(&polynomial, other_values + num_values - 1)
+        };
+
+        let local = self.policy.placement(num_slots, num_values, offset);
+        let mut best = (local, score(local));
+        // Only placements that leave both sides with room for their slots.
+        let first = (num_slots + offset).saturating_sub(num_values);
+        for placement in first..=offset.min(num_slots - 1) {
+            let value = score(placement);
+            if value - best.1 > best.1.abs() * 1e-12 {
+                best = (placement, value);
+            }
+        }
+        if best.0 != local {
+            self.disagreements.fetch_add(1, Ordering::Relaxed);
+        }
+        first_slot + best.0
+    }
+
+    fn stats(&self) -> Vec<(String, u64)> {
+        vec![(
+            "disagreements with the gap-local policy".to_string(),
+            self.disagreements.load(Ordering::Relaxed),
+        )]
+    }
+}
+
+/// Picks the slot whose random continuations win most often, playing each
+/// continuation out with a base strategy.
+///
+/// Every candidate slot is tried on the same `num_rollouts` draw sequences, so the
+/// comparison between slots is not blurred by luck of the draw. Ties in wins are
+/// broken by the numbers placed. The draws come from the game's generator, so
+/// seeded games repeat them.
+pub struct RolloutStrategy {
+    base: Arc<dyn Strategy>,
+    num_rollouts: usize,
+    significance_gate: bool,
+    overrules: AtomicU64,
+}
+
+impl RolloutStrategy {
+    pub fn new(base: Arc<dyn Strategy>, num_rollouts: usize) -> Self {
+        Self {
+            base,
+            num_rollouts,
+            significance_gate: false,
+            overrules: AtomicU64::new(0),
+        }
+    }
+
+    /// Keeps the base strategy's own choice unless the best slot wins significantly
+    /// more often on the same draws. Wins are rare early in a game, so this mostly
+    /// leaves the early decisions to the base strategy.
+    pub fn with_significance_gate(mut self) -> Self {
+        self.significance_gate = true;
+        self
+    }
+}
+
+impl Strategy for RolloutStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        mut rng: &mut dyn RngCore,
+    ) -> usize {
+        let mut drawn: Vec<i32> = current_board.iter().flatten().copied().collect();
+        drawn.push(number);
+        let remaining = current_board.len() - drawn.len();
+        let rollouts: Vec<Vec<i32>> = (0..self.num_rollouts)
+            .map(|_| draw_more_numbers(&drawn, remaining, &mut rng))
+            .collect();
+
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        let base_slot = choose_placement(self.base.as_ref(), &gap, number, current_board, rng);
+
+        let mut best = (first_slot, (0, 0));
+        let mut board = current_board.to_vec();
+        let mut won = vec![Vec::new(); last_slot + 1];
+        for slot in first_slot..=last_slot {
+            let mut placed = 0;
+            for numbers in &rollouts {
+                board.copy_from_slice(current_board);
+                board[slot] = Some(number);
+                let count = play_out(self.base.as_ref(), &mut board, numbers, rng);
+                won[slot].push(count == remaining);
+                placed += count;
+            }
+            let wins = won[slot].iter().filter(|&&win| win).count();
+            if slot == first_slot || (wins, placed) > best.1 {
+                best = (slot, (wins, placed));
+            }
+        }
+
+        if best.0 == base_slot {
+            return base_slot;
+        }
+        if self.significance_gate {
+            // McNemar's test at about two standard deviations.
+            let (better, worse) = won[best.0].iter().zip(&won[base_slot]).fold(
+                (0, 0),
+                |(better, worse), (&best_won, &base_won)| {
+                    (
+                        better + (best_won && !base_won) as usize,
+                        worse + (base_won && !best_won) as usize,
+                    )
+                },
+            );
+            if better as f64 - worse as f64 <= 2.0 * ((better + worse) as f64).sqrt() {
+                return base_slot;
+            }
+        }
+        self.overrules.fetch_add(1, Ordering::Relaxed);
+        best.0
+    }
+
+    fn stats(&self) -> Vec<(String, u64)> {
+        vec![(
+            "overrules of the base strategy".to_string(),
+            self.overrules.load(Ordering::Relaxed),
+        )]
+    }
+}
+
+/// Searches the game tree from every decision with Monte Carlo tree search, so it
+/// plays any board without a table or a gap-local model of the game. See
+/// `mcts::search` for how the search runs.
+pub struct MctsStrategy {
+    base: Arc<dyn Strategy>,
+    objective: Objective,
+    iterations: usize,
+    exploration: f64,
+}
+
+impl MctsStrategy {
+    pub fn new(
+        base: Arc<dyn Strategy>,
+        objective: Objective,
+        iterations: usize,
+        exploration: f64,
+    ) -> Self {
+        Self {
+            base,
+            objective,
+            iterations,
+            exploration,
+        }
+    }
+}
+
+impl Strategy for MctsStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        mut rng: &mut dyn RngCore,
+    ) -> usize {
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        mcts::search(
+            current_board,
+            &gap,
+            number,
+            self.base.as_ref(),
+            self.objective,
+            self.iterations,
+            self.exploration,
+            &mut rng,
+        )
+    }
+}
+
+/// Places numbers by a `ParametricPolicy`, a handful of weights in place of a table.
+pub struct ParametricStrategy {
+    policy: ParametricPolicy,
+}
+
+impl ParametricStrategy {
+    pub fn new(weights: [f64; NUM_WEIGHTS], max_slots: usize) -> Self {
+        Self {
+            policy: ParametricPolicy::new(weights, max_slots),
+        }
+    }
+}
+
+impl Strategy for ParametricStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        first_slot + self.policy.placement(num_slots, num_values, offset)
+    }
+
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        Some(self.policy.placement_scores(num_slots, num_values, offset))
+    }
+}
+
+/// Follows a base strategy, but with probability `epsilon` places the number in a
+/// uniformly random feasible slot of its gap instead, one that still leaves both sides
+/// as many values as slots.
+pub struct EpsilonGreedyStrategy {
+    base: Arc<dyn Strategy>,
+    epsilon: f64,
+    random_placements: AtomicU64,
+}
+
+impl EpsilonGreedyStrategy {
+    pub fn new(base: Arc<dyn Strategy>, epsilon: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&epsilon),
+            "EpsilonGreedyStrategy requires an epsilon between 0 and 1"
+        );
+        Self {
+            base,
+            epsilon,
+            random_placements: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Strategy for EpsilonGreedyStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        let placements = feasible_placements(num_slots, num_values, offset);
+        if num_slots > 1 && !placements.is_empty() && rng.random_bool(self.epsilon) {
+            self.random_placements.fetch_add(1, Ordering::Relaxed);
+            return first_slot + rng.random_range(placements);
+        }
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        choose_placement(self.base.as_ref(), &gap, number, current_board, rng)
+    }
+
+    fn covers_gap(&self, num_slots: usize, num_values: usize) -> bool {
+        self.base.covers_gap(num_slots, num_values)
+    }
+
+    fn stats(&self) -> Vec<(String, u64)> {
+        vec![(
+            "random placements".to_string(),
+            self.random_placements.load(Ordering::Relaxed),
+        )]
+    }
+}
+
+/// Picks each placement with probability proportional to `P^(1 / temperature)`, where
+/// `P` is the exact win probability of the gap after it, so the softmax of the log
+/// win probabilities. As the temperature falls to zero this becomes the optimal
+/// policy, and at one it follows the win probabilities themselves.
+pub struct SoftmaxStrategy {
+    solver: SolverStrategy,
+    temperature: f64,
+    departures: AtomicU64,
+}
+
+impl SoftmaxStrategy {
+    pub fn new(temperature: f64, max_slots: usize) -> Self {
+        Self {
+            solver: SolverStrategy::new(max_slots),
+            temperature,
+            departures: AtomicU64::new(0),
+        }
+    }
+
+    /// The probability of picking each placement of the number at `offset`.
+    fn choice_probabilities(&self, num_slots: usize, num_values: usize, offset: usize) -> Vec<f64> {
+        let best = self.solver.placement(num_slots, num_values, offset);
+        let probabilities = self
+            .solver
+            .placement_win_probabilities(num_slots, num_values, offset);
+        let mut weights = vec![0.0; num_slots];
+        if self.temperature <= 0.0 || probabilities[best] == 0.0 {
+            weights[best] = 1.0;
+            return weights;
+        }
+        // Scale by the best probability first so the powers cannot all underflow.
+        for (weight, probability) in weights.iter_mut().zip(&probabilities) {
+            *weight = (probability / probabilities[best]).powf(1.0 / self.temperature);
+        }
+        let total: f64 = weights.iter().sum();
+        for weight in &mut weights {
+            *weight /= total;
+        }
+        weights
+    }
+}
+
+impl Strategy for SoftmaxStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        let best = self.solver.placement(num_slots, num_values, offset);
+        let weights = self.choice_probabilities(num_slots, num_values, offset);
+        let mut target = rng.random_range(0.0..1.0);
+        let mut placement = best;
+        for (candidate, &weight) in weights.iter().enumerate() {
+            if weight > 0.0 {
+                placement = candidate;
+                if target < weight {
+                    break;
+                }
+                target -= weight;
+            }
+        }
+        if placement != best {
+            self.departures.fetch_add(1, Ordering::Relaxed);
+        }
+        first_slot + placement
+    }
+
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let num_slots = last_slot - first_slot + 1;
+        let num_values = (upper - lower - 1) as usize;
+        let offset = (number - lower - 1) as usize;
+        Some(self.choice_probabilities(num_slots, num_values, offset))
+    }
+
+    fn stats(&self) -> Vec<(String, u64)> {
+        vec![(
+            "departures from the optimum".to_string(),
+            self.departures.load(Ordering::Relaxed),
+        )]
+    }
+}
+
+/// Asks one of several strategies for each placement, picking it at random with
+/// probability proportional to its weight.
+///
+/// The choice is made afresh for every placement rather than once per game, so a game
+/// mixes the strategies' moves.
+pub struct MixtureStrategy {
+    components: Vec<(f64, Arc<dyn Strategy>)>,
+    total_weight: f64,
+}
+
+impl MixtureStrategy {
+    pub fn new(components: Vec<(f64, Arc<dyn Strategy>)>) -> Self {
+        let total_weight = components.iter().map(|&(weight, _)| weight).sum();
+        assert!(
+            total_weight > 0.0,
+            "MixtureStrategy requires a positive total weight"
+        );
+        Self {
+            components,
+            total_weight,
+        }
+    }
+}
+
+impl Strategy for MixtureStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        let mut target = rng.random_range(0.0..self.total_weight);
+        let mut chosen = &self.components[0].1;
+        for (weight, component) in &self.components {
+            if *weight > 0.0 {
+                chosen = component;
+                if target < *weight {
+                    break;
+                }
+                target -= weight;
+            }
+        }
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        choose_placement(chosen.as_ref(), &gap, number, current_board, rng)
+    }
+
+    fn covers_gap(&self, num_slots: usize, num_values: usize) -> bool {
+        self.components
+            .iter()
+            .all(|(_, component)| component.covers_gap(num_slots, num_values))
+    }
+}
+
+/// The index of the highest score, the first on ties.
+pub fn highest_score(scores: &[f64]) -> usize {
+    let mut best = (0, f64::NEG_INFINITY);
+    for (index, &score) in scores.iter().enumerate() {
+        if score > best.1 {
+            best = (index, score);
+        }
+    }
+    best.0
+}
+
+/// Adds up the slot scores of several strategies, weighted, and places the number in
+/// the slot with the highest total.
+///
+/// Each member's scores are scaled to sum to one first, so its say does not depend on
+/// the scale of its scores. A member without scores, or whose move the engine would
+/// force, puts all of its weight on the slot it would choose.
+pub struct EnsembleStrategy {
+    members: Vec<(f64, Arc<dyn Strategy>)>,
+}
+
+impl EnsembleStrategy {
+    pub fn new(members: Vec<(f64, Arc<dyn Strategy>)>) -> Self {
+        Self { members }
+    }
+
+    fn combined_scores(
+        &self,
+        gap: &Gap,
+        number: i32,
+        current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
+    ) -> Vec<f64> {
+        let mut combined = vec![0.0; gap.last_index - gap.first_index + 1];
+        for (weight, member) in &self.members {
+            let scores = if member.want_full_control() || forced_placement(gap, number).is_none() {
+                member.slot_scores(
+                    gap.lower,
+                    gap.upper,
+                    gap.first_index,
+                    gap.last_index,
+                    number,
+                    current_board,
+                )
+            } else {
+                None
+            };
+            let total: f64 = scores.iter().flatten().sum();
+            match scores {
+                Some(scores) if total > 0.0 => {
+                    for (combined, score) in combined.iter_mut().zip(scores) {
+                        *combined += weight * score / total;
+                    }
+                }
+                _ => {
+                    let slot = choose_placement(member.as_ref(), gap, number, current_board, rng);
+                    combined[slot - gap.first_index] += weight;
+                }
+            }
+        }
+        combined
+    }
+}
+
+impl Strategy for EnsembleStrategy {
+    fn want_full_control(&self) -> bool {
+        true
+    }
+
+    fn choose_slot(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> usize {
+        self.choose_slot_with_rng(
+            lower,
+            upper,
+            first_slot,
+            last_slot,
+            number,
+            current_board,
+            &mut rand::rng(),
+        )
+    }
+
+    // Takes the generator only to pass it on to randomized members.
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        first_slot + highest_score(&self.combined_scores(&gap, number, current_board, rng))
+    }
+
+    fn slot_scores(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        let gap = Gap {
+            lower,
+            upper,
+            first_index: first_slot,
+            last_index: last_slot,
+        };
+        // Randomized members draw from a generator seeded by the board and the number,
+        // so the same decision always gets the same scores.
+        let drawn: Vec<i32> = current_board
+            .iter()
+            .flatten()
+            .copied()
+            .chain([number])
+            .collect();
+        let mut rng = StdRng::seed_from_u64(game_seed(&drawn));
+        Some(self.combined_scores(&gap, number, current_board, &mut rng))
+    }
+
+    fn covers_gap(&self, num_slots: usize, num_values: usize) -> bool {
+        self.members
+            .iter()
+            .all(|(_, member)| member.covers_gap(num_slots, num_values))
     }
 }
@@ -1,3 +1,5 @@
+use rand::RngCore;
+
 /// The `Strategy` trait defines how to choose a slot given a valid gap.
 /// The parameters:
 ///   - `lower`: the number in the slot immediately to the left (or a lower bound).
@@ -18,7 +20,55 @@ pub trait Strategy: Send + Sync {
         current_board: &[Option<i32>],
     ) -> usize;
 
+    /// Chooses a slot like `choose_slot`, drawing any random choices from `rng`.
+    ///
+    /// The engine passes a generator seeded for each game, so randomized strategies
+    /// override this to make the same choices whenever a game is replayed, and keep
+    /// `choose_slot` for callers without a generator. Deterministic strategies keep
+    /// the default, which ignores `rng`.
+    #[allow(clippy::too_many_arguments)]
+    fn choose_slot_with_rng(
+        &self,
+        lower: i32,
+        upper: i32,
+        first_slot: usize,
+        last_slot: usize,
+        number: i32,
+        current_board: &[Option<i32>],
+        _rng: &mut dyn RngCore,
+    ) -> usize {
+        self.choose_slot(lower, upper, first_slot, last_slot, number, current_board)
+    }
+
+    /// A score for every slot of the gap, in order from `first_slot`, for tools that
+    /// explain decisions, ensembles and analyses of near-ties. Scores are non-negative
+    /// and higher is better, like probabilities or likelihoods: `choose_slot` picks a
+    /// slot with the highest score, or for a randomized strategy, picks each slot with
+    /// the probability given. Strategies that do not score slots keep the default.
+    fn slot_scores(
+        &self,
+        _lower: i32,
+        _upper: i32,
+        _first_slot: usize,
+        _last_slot: usize,
+        _number: i32,
+        _current_board: &[Option<i32>],
+    ) -> Option<Vec<f64>> {
+        None
+    }
+
     fn want_full_control(&self) -> bool {
         false
     }
+
+    /// Whether the strategy can choose a slot in a gap with `num_slots` slots and
+    /// `num_values` values. Strategies that can decide any gap keep the default.
+    fn covers_gap(&self, _num_slots: usize, _num_values: usize) -> bool {
+        true
+    }
+
+    /// Named counters the strategy keeps while playing, reported after a run.
+    fn stats(&self) -> Vec<(String, u64)> {
+        Vec::new()
+    }
 }
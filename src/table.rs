@@ -0,0 +1,1216 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use crate::binary_table::{self, BinaryHeader, GapRows};
+use crate::engine::{
+    choose_placement, GameConfig, Gap, GAME_CONFIG, LOWER_BOUND, NUM_SLOTS, NUM_VALUES, UPPER_BOUND,
+};
+use crate::solver::split_probability;
+use crate::strategy::Strategy;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LookupKey {
+    pub num_slots: usize,
+    pub num_values: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    pub upper_bound: i32,
+    pub placement_index: usize,
+}
+
+/// A row of a table file.
+#[derive(Debug, Clone, Copy)]
+pub struct TableRow {
+    /// The 1-based line of the file the row was read from.
+    pub line: usize,
+    pub key: LookupKey,
+    pub candidate: Candidate,
+}
+
+/// A `value num_slots num_values win_probability` line of a table file, recording
+/// the probability of winning a gap of that shape when the table is followed.
+#[derive(Debug, Clone, Copy)]
+pub struct TableValue {
+    /// The 1-based line of the file the value was read from.
+    pub line: usize,
+    pub key: LookupKey,
+    pub win_probability: f64,
+}
+
+/// Everything read from a table file, in file order.
+#[derive(Debug, Clone, Default)]
+pub struct TableFile {
+    pub metadata: TableMetadata,
+    pub rows: Vec<TableRow>,
+    pub values: Vec<TableValue>,
+}
+
+/// What a table records about how it was built. Every field is optional so tables
+/// written before the header existed still load.
+///
+/// In the text format the metadata is a block of `# key value` lines before the
+/// rows, with the keys `slots`, `values`, `objective`, `generator` and `checksum`.
+/// The checksum is the FNV-1a hash of the whole file with its own line written as
+/// `CHECKSUM_PLACEHOLDER`, in hex, so it covers the header as well as the rows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableMetadata {
+    /// The game the table was built for.
+    pub config: Option<GameConfig>,
+    /// What the table's placements optimise, e.g. `win`.
+    pub objective: Option<String>,
+    /// How the table was made, e.g. the command that wrote it.
+    pub generator: Option<String>,
+}
+
+impl TableMetadata {
+    /// Whether the table can be played in a game shaped like `config`. Tables that
+    /// do not record their game are assumed to fit.
+    pub fn fits(&self, config: GameConfig) -> bool {
+        self.config
+            .is_none_or(|table_config| table_config == config)
+    }
+}
+
+impl fmt::Display for TableMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.config {
+            Some(config) => write!(
+                f,
+                "{} slots, {} values",
+                config.num_slots, config.num_values
+            )?,
+            None => write!(f, "game not recorded")?,
+        }
+        write!(
+            f,
+            ", objective {}, generator {}",
+            self.objective.as_deref().unwrap_or("not recorded"),
+            self.generator.as_deref().unwrap_or("not recorded")
+        )
+    }
+}
+
+/// An error loading a lookup table.
+#[derive(Debug)]
+pub enum TableError {
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    Binary {
+        path: String,
+        message: String,
+    },
+    Invalid {
+        path: String,
+        problems: Vec<TableProblem>,
+    },
+    /// The table was built for a different game than the engine plays.
+    ConfigMismatch {
+        path: String,
+        table: GameConfig,
+        engine: GameConfig,
+    },
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableError::Io { path, source } => write!(f, "{}: {}", path, source),
+            TableError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path, line, message),
+            TableError::Binary { path, message } => write!(f, "{}: {}", path, message),
+            TableError::Invalid { path, problems } => {
+                write!(f, "{}: {} problems", path, problems.len())?;
+                for problem in problems.iter().take(10) {
+                    write!(f, "\n  {}", problem)?;
+                }
+                if problems.len() > 10 {
+                    write!(f, "\n  ...")?;
+                }
+                Ok(())
+            }
+            TableError::ConfigMismatch {
+                path,
+                table,
+                engine,
+            } => write!(
+                f,
+                "{}: table was built for {} slots and {} values but the game has {} slots and {} values",
+                path, table.num_slots, table.num_values, engine.num_slots, engine.num_values
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TableError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Something wrong with the contents of a table.
+#[derive(Debug, Clone)]
+pub enum TableProblem {
+    /// A gap shape the table should cover has no rows.
+    MissingGap { key: LookupKey },
+    /// A row places the number outside its gap.
+    PlacementOutOfRange {
+        line: usize,
+        key: LookupKey,
+        placement_index: usize,
+    },
+    /// A row's threshold is not above the previous row's for the same gap shape.
+    ThresholdNotIncreasing {
+        line: usize,
+        key: LookupKey,
+        upper_bound: i32,
+    },
+    /// The last threshold of a gap shape does not cover its largest offset.
+    LastThreshold {
+        line: usize,
+        key: LookupKey,
+        upper_bound: i32,
+    },
+    /// A row's gap shape is larger than any gap of the engine's game.
+    GapTooLarge { line: usize, key: LookupKey },
+    /// A win probability is not between 0 and 1.
+    ValueOutOfRange {
+        line: usize,
+        key: LookupKey,
+        win_probability: f64,
+    },
+}
+
+impl fmt::Display for TableProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableProblem::MissingGap { key } => write!(
+                f,
+                "no rows for gap with {} slots and {} values",
+                key.num_slots, key.num_values
+            ),
+            TableProblem::PlacementOutOfRange {
+                line,
+                key,
+                placement_index,
+            } => write!(
+                f,
+                "line {}: placement {} is outside a gap with {} slots",
+                line, placement_index, key.num_slots
+            ),
+            TableProblem::ThresholdNotIncreasing {
+                line,
+                key,
+                upper_bound,
+            } => write!(
+                f,
+                "line {}: threshold {} for gap with {} slots and {} values is not above the previous row",
+                line, upper_bound, key.num_slots, key.num_values
+            ),
+            TableProblem::LastThreshold {
+                line,
+                key,
+                upper_bound,
+            } => write!(
+                f,
+                "line {}: last threshold for gap with {} slots and {} values is {} but should be {}",
+                line,
+                key.num_slots,
+                key.num_values,
+                upper_bound,
+                key.num_values as i32 - 1
+            ),
+            TableProblem::GapTooLarge { line, key } => write!(
+                f,
+                "line {}: gap with {} slots and {} values is larger than a game with {} slots and {} values",
+                line, key.num_slots, key.num_values, NUM_SLOTS, NUM_VALUES
+            ),
+            TableProblem::ValueOutOfRange {
+                line,
+                key,
+                win_probability,
+            } => write!(
+                f,
+                "line {}: win probability {} for gap with {} slots and {} values is not between 0 and 1",
+                line, win_probability, key.num_slots, key.num_values
+            ),
+        }
+    }
+}
+
+/// Reads a table file in either the text or the binary format.
+///
+/// Rows of a binary table are numbered in place of lines, and its win probabilities
+/// take the number of the first row of their gap shape.
+pub fn read_table(file_path: &str) -> Result<TableFile, TableError> {
+    let bytes = std::fs::read(file_path).map_err(|source| TableError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    parse_table(&bytes, file_path)
+}
+
+/// Parses a table held in memory. `file_path` names it in errors.
+fn parse_table(bytes: &[u8], file_path: &str) -> Result<TableFile, TableError> {
+    let io_error = |source| TableError::Io {
+        path: file_path.to_string(),
+        source,
+    };
+
+    if binary_table::is_binary(bytes) {
+        let (header, gaps) = binary_table::decode(bytes).map_err(|message| TableError::Binary {
+            path: file_path.to_string(),
+            message,
+        })?;
+        let mut rows = Vec::new();
+        let mut values = Vec::new();
+        for gap in gaps {
+            let key = LookupKey {
+                num_slots: gap.num_slots,
+                num_values: gap.num_values,
+            };
+            if let Some(win_probability) = gap.win_probability {
+                values.push(TableValue {
+                    line: rows.len() + 1,
+                    key,
+                    win_probability,
+                });
+            }
+            for (placement_index, upper_bound) in gap.rows {
+                rows.push(TableRow {
+                    line: rows.len() + 1,
+                    key,
+                    candidate: Candidate {
+                        upper_bound,
+                        placement_index,
+                    },
+                });
+            }
+        }
+        let metadata = TableMetadata {
+            config: (header.max_slots > 0 && header.max_values > 0).then_some(GameConfig {
+                num_slots: header.max_slots,
+                num_values: header.max_values,
+            }),
+            objective: Some(header.objective).filter(|text| !text.is_empty()),
+            generator: Some(header.generator).filter(|text| !text.is_empty()),
+        };
+        return Ok(TableFile {
+            metadata,
+            rows,
+            values,
+        });
+    }
+
+    let (metadata, header_lines, body) = parse_header(bytes, file_path)?;
+    let mut rows = Vec::new();
+    let mut values = Vec::new();
+    for (line_index, line) in body.lines().enumerate() {
+        let line_index = line_index + header_lines;
+        let line = line.map_err(io_error)?;
+        let parse_error = |message: String| TableError::Parse {
+            path: file_path.to_string(),
+            line: line_index + 1,
+            message,
+        };
+
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.first() == Some(&"value") {
+            let [_, num_slots, num_values, win_probability] = parts[..] else {
+                return Err(parse_error(format!(
+                    "expected 'value num_slots num_values win_probability' but found {} fields",
+                    parts.len()
+                )));
+            };
+            let number = |text: &str, name: &str| {
+                text.parse::<usize>().map_err(|_| {
+                    parse_error(format!("{} '{}' is not a non-negative number", name, text))
+                })
+            };
+            values.push(TableValue {
+                line: line_index + 1,
+                key: LookupKey {
+                    num_slots: number(num_slots, "num_slots")?,
+                    num_values: number(num_values, "num_values")?,
+                },
+                win_probability: win_probability.parse().map_err(|_| {
+                    parse_error(format!(
+                        "win_probability '{}' is not a number",
+                        win_probability
+                    ))
+                })?,
+            });
+            continue;
+        }
+        if parts.len() != 4 {
+            return Err(parse_error(format!(
+                "expected 'num_slots num_values placement_index upper_bound' but found {} fields",
+                parts.len()
+            )));
+        }
+        let field = |index: usize, name: &str| {
+            parts[index].parse::<u32>().map_err(|_| {
+                parse_error(format!(
+                    "{} '{}' is not a non-negative number",
+                    name, parts[index]
+                ))
+            })
+        };
+        let num_slots = field(0, "num_slots")? as usize;
+        let num_values = field(1, "num_values")? as usize;
+        let placement_index = field(2, "placement_index")? as usize;
+        let upper_bound = i32::try_from(field(3, "upper_bound")?)
+            .map_err(|_| parse_error(format!("upper_bound '{}' is too large", parts[3])))?;
+
+        rows.push(TableRow {
+            line: line_index + 1,
+            key: LookupKey {
+                num_slots,
+                num_values,
+            },
+            candidate: Candidate {
+                upper_bound,
+                placement_index,
+            },
+        });
+    }
+    Ok(TableFile {
+        metadata,
+        rows,
+        values,
+    })
+}
+
+/// The checksum line of a text table as it is hashed.
+const CHECKSUM_PLACEHOLDER: &[u8] = b"# checksum 0000000000000000\n";
+
+/// Splits the header block off a text table, verifying its checksum if it has one.
+///
+/// Returns the metadata, the number of header lines and the rest of the file.
+fn parse_header<'a>(
+    bytes: &'a [u8],
+    file_path: &str,
+) -> Result<(TableMetadata, usize, &'a [u8]), TableError> {
+    let mut metadata = TableMetadata::default();
+    let mut slots = None;
+    let mut values = None;
+    let mut expected_checksum = None;
+    let mut body = bytes;
+    let mut header_lines = 0;
+    while body.starts_with(b"#") {
+        let end = body
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(body.len(), |i| i + 1);
+        let parse_error = |message: String| TableError::Parse {
+            path: file_path.to_string(),
+            line: header_lines + 1,
+            message,
+        };
+        let line = std::str::from_utf8(&body[1..end])
+            .map_err(|_| parse_error("header line is not UTF-8".to_string()))?
+            .trim();
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| parse_error(format!("{} '{}' is not a number", key, value)))
+        };
+        match key {
+            "slots" => slots = Some(number()?),
+            "values" => values = Some(number()?),
+            "objective" => metadata.objective = Some(value.to_string()),
+            "generator" => metadata.generator = Some(value.to_string()),
+            "checksum" => {
+                let checksum = u64::from_str_radix(value, 16)
+                    .map_err(|_| parse_error(format!("checksum '{}' is not hex", value)))?;
+                let start = bytes.len() - body.len();
+                expected_checksum = Some((header_lines + 1, start..start + end, checksum));
+            }
+            // Anything else is a comment.
+            _ => {}
+        }
+        body = &body[end..];
+        header_lines += 1;
+    }
+
+    metadata.config = match (slots, values) {
+        (Some(num_slots), Some(num_values)) => Some(GameConfig {
+            num_slots,
+            num_values,
+        }),
+        (None, None) => None,
+        _ => {
+            return Err(TableError::Parse {
+                path: file_path.to_string(),
+                line: 1,
+                message: "header needs both slots and values".to_string(),
+            })
+        }
+    };
+    if let Some((line, range, checksum)) = expected_checksum {
+        let mut hashed = bytes.to_vec();
+        hashed.splice(range, CHECKSUM_PLACEHOLDER.iter().copied());
+        let actual = binary_table::checksum(&hashed);
+        if actual != checksum {
+            return Err(TableError::Parse {
+                path: file_path.to_string(),
+                line,
+                message: format!(
+                    "checksum {:016x} does not match the table ({:016x})",
+                    checksum, actual
+                ),
+            });
+        }
+    }
+    Ok((metadata, header_lines, body))
+}
+
+/// Finds every problem with a table's rows: gap shapes too large for the engine's
+/// game, placements outside their gap, thresholds that do not increase or do not
+/// end at the last offset, and gap shapes of the engine's game that have no rows.
+pub fn check_rows(rows: &[TableRow]) -> Vec<TableProblem> {
+    let mut problems = Vec::new();
+    let mut last_rows: HashMap<LookupKey, &TableRow> = HashMap::new();
+    for row in rows {
+        let key = row.key;
+        // The table is laid out densely up to its largest gap, so a stray huge one
+        // must not get that far.
+        if key.num_slots > NUM_SLOTS || key.num_values > NUM_VALUES {
+            problems.push(TableProblem::GapTooLarge {
+                line: row.line,
+                key,
+            });
+            continue;
+        }
+        if row.candidate.placement_index >= key.num_slots {
+            problems.push(TableProblem::PlacementOutOfRange {
+                line: row.line,
+                key,
+                placement_index: row.candidate.placement_index,
+            });
+        }
+        if let Some(previous) = last_rows.get(&key) {
+            if row.candidate.upper_bound <= previous.candidate.upper_bound {
+                problems.push(TableProblem::ThresholdNotIncreasing {
+                    line: row.line,
+                    key,
+                    upper_bound: row.candidate.upper_bound,
+                });
+            }
+        }
+        last_rows.insert(key, row);
+    }
+
+    let mut last_rows: Vec<&TableRow> = last_rows.into_values().collect();
+    last_rows.sort_by_key(|row| row.line);
+    for row in last_rows {
+        if row.candidate.upper_bound != row.key.num_values as i32 - 1 {
+            problems.push(TableProblem::LastThreshold {
+                line: row.line,
+                key: row.key,
+                upper_bound: row.candidate.upper_bound,
+            });
+        }
+    }
+
+    let covered: HashSet<LookupKey> = rows.iter().map(|row| row.key).collect();
+    for num_slots in 1..=NUM_SLOTS {
+        for num_values in num_slots..=NUM_VALUES {
+            let key = LookupKey {
+                num_slots,
+                num_values,
+            };
+            if !covered.contains(&key) {
+                problems.push(TableProblem::MissingGap { key });
+            }
+        }
+    }
+    problems
+}
+
+/// Finds every win probability of a table that is not between 0 and 1.
+pub fn check_values(values: &[TableValue]) -> Vec<TableProblem> {
+    values
+        .iter()
+        .filter(|value| !(0.0..=1.0).contains(&value.win_probability))
+        .map(|value| TableProblem::ValueOutOfRange {
+            line: value.line,
+            key: value.key,
+            win_probability: value.win_probability,
+        })
+        .collect()
+}
+
+/// An offset of a gap shape where two tables choose different placements.
+#[derive(Debug, Clone, Copy)]
+pub struct TableDifference {
+    pub key: LookupKey,
+    pub offset: usize,
+    /// The placement in each table, or `None` if the table has no entry for the gap shape.
+    pub placements: [Option<usize>; 2],
+}
+
+/// A placement policy stored as `num_slots num_values placement_index upper_bound` rows.
+///
+/// For each gap shape the rows are thresholds: an offset (0-indexed among the gap's
+/// values) goes to the placement of the first row whose `upper_bound` is at least
+/// the offset.
+///
+/// The rows are stored densely by gap shape so a query is an index computation
+/// followed by a binary search over that shape's thresholds.
+///
+/// A table can also record the probability of winning each gap shape when it is
+/// followed, written as `value num_slots num_values win_probability` lines before
+/// the shape's rows. Those make it a value function: the win probability of every
+/// placement follows from the values of the two gaps it creates.
+pub struct LookupTable {
+    metadata: TableMetadata,
+    max_slots: usize,
+    max_values: usize,
+    /// The rows of gap shape `(num_slots, num_values)` are
+    /// `starts[i]..starts[i + 1]` with `i = num_slots * (max_values + 1) + num_values`.
+    starts: Vec<u32>,
+    /// Row thresholds, sorted within each gap shape.
+    upper_bounds: Vec<i32>,
+    placements: Vec<u32>,
+    /// Win probabilities indexed like `starts` (NaN where unknown), or empty if the
+    /// table has none.
+    win_probabilities: Vec<f64>,
+}
+
+impl LookupTable {
+    /// Loads a table from a text file, rejecting rows that could place a number
+    /// outside its gap. Gap shapes the file does not cover are left out.
+    pub fn load(file_path: &str) -> Result<Self, TableError> {
+        let bytes = std::fs::read(file_path).map_err(|source| TableError::Io {
+            path: file_path.to_string(),
+            source,
+        })?;
+        Self::from_bytes(&bytes, file_path)
+    }
+
+    /// Loads one of the tables bundled with the repository (`strategy.txt` or
+    /// `strategyint.txt`): from the executable when built with the `embedded-tables`
+    /// feature, otherwise from the current directory.
+    pub fn load_bundled(file_name: &str) -> Result<Self, TableError> {
+        #[cfg(feature = "embedded-tables")]
+        {
+            const EMBEDDED_TABLES: [(&str, &[u8]); 2] = [
+                (
+                    "strategy.txt",
+                    include_bytes!(concat!(env!("OUT_DIR"), "/strategy.bin")),
+                ),
+                (
+                    "strategyint.txt",
+                    include_bytes!(concat!(env!("OUT_DIR"), "/strategyint.bin")),
+                ),
+            ];
+            if let Some((_, bytes)) = EMBEDDED_TABLES.iter().find(|(name, _)| *name == file_name) {
+                return Self::from_bytes(bytes, file_name);
+            }
+        }
+        Self::load(file_name)
+    }
+
+    /// Parses a table held in memory, in either format. `file_path` names it in errors.
+    pub fn from_bytes(bytes: &[u8], file_path: &str) -> Result<Self, TableError> {
+        let TableFile {
+            metadata,
+            rows,
+            values,
+        } = parse_table(bytes, file_path)?;
+        let problems: Vec<TableProblem> = check_rows(&rows)
+            .into_iter()
+            .chain(check_values(&values))
+            .filter(|problem| !matches!(problem, TableProblem::MissingGap { .. }))
+            .collect();
+        if !problems.is_empty() {
+            return Err(TableError::Invalid {
+                path: file_path.to_string(),
+                problems,
+            });
+        }
+
+        // Group the rows by gap shape.
+        let mut table: HashMap<LookupKey, Vec<Candidate>> = HashMap::new();
+        for row in rows {
+            table.entry(row.key).or_default().push(row.candidate);
+        }
+        let mut table = Self::from_gaps(table);
+        table.metadata = metadata;
+        if !values.is_empty() {
+            let values: HashMap<LookupKey, f64> = values
+                .iter()
+                .map(|value| (value.key, value.win_probability))
+                .collect();
+            table.set_win_probabilities(|key| values.get(&key).copied().unwrap_or(f64::NAN));
+        }
+        Ok(table)
+    }
+
+    /// Lays out rows grouped by gap shape densely, sorting each shape's thresholds.
+    fn from_gaps(gaps: HashMap<LookupKey, Vec<Candidate>>) -> Self {
+        let max_slots = gaps.keys().map(|key| key.num_slots).max().unwrap_or(0);
+        let max_values = gaps.keys().map(|key| key.num_values).max().unwrap_or(0);
+        let num_rows: usize = gaps.values().map(Vec::len).sum();
+
+        let mut table = Self {
+            metadata: TableMetadata::default(),
+            max_slots,
+            max_values,
+            starts: Vec::with_capacity((max_slots + 1) * (max_values + 1) + 1),
+            upper_bounds: Vec::with_capacity(num_rows),
+            placements: Vec::with_capacity(num_rows),
+            win_probabilities: Vec::new(),
+        };
+        for num_slots in 0..=max_slots {
+            for num_values in 0..=max_values {
+                table.starts.push(table.upper_bounds.len() as u32);
+                let key = LookupKey {
+                    num_slots,
+                    num_values,
+                };
+                if let Some(candidates) = gaps.get(&key) {
+                    let mut candidates = candidates.clone();
+                    candidates.sort_by_key(|candidate| candidate.upper_bound);
+                    for candidate in candidates {
+                        table.upper_bounds.push(candidate.upper_bound);
+                        table.placements.push(candidate.placement_index as u32);
+                    }
+                }
+            }
+        }
+        table.starts.push(table.upper_bounds.len() as u32);
+        table
+    }
+
+    /// Builds a table by asking `policy` for the placement of every offset of every
+    /// gap with up to `max_slots` slots and `max_values` values, merging runs of
+    /// offsets with the same placement into a single row. The table records the game
+    /// it was built for but no objective or generator.
+    pub fn from_policy(
+        max_slots: usize,
+        max_values: usize,
+        policy: impl Fn(LookupKey, usize) -> usize + Sync,
+    ) -> Self {
+        let keys: Vec<LookupKey> = (1..=max_slots)
+            .flat_map(|num_slots| {
+                (num_slots..=max_values).map(move |num_values| LookupKey {
+                    num_slots,
+                    num_values,
+                })
+            })
+            .collect();
+        let mut table = Self::from_shapes(keys, policy);
+        table.metadata.config = Some(GameConfig {
+            num_slots: max_slots,
+            num_values: max_values,
+        });
+        table
+    }
+
+    /// Builds a table covering only the gap shapes in `keys`, asking `policy` for the
+    /// placement of every offset like `from_policy`. The table records no metadata.
+    pub fn from_shapes(
+        keys: Vec<LookupKey>,
+        policy: impl Fn(LookupKey, usize) -> usize + Sync,
+    ) -> Self {
+        let table = keys
+            .into_par_iter()
+            .map(|key| {
+                let mut candidates: Vec<Candidate> = Vec::new();
+                for offset in 0..key.num_values {
+                    let placement_index = policy(key, offset);
+                    match candidates.last_mut() {
+                        Some(last) if last.placement_index == placement_index => {
+                            last.upper_bound = offset as i32;
+                        }
+                        _ => candidates.push(Candidate {
+                            upper_bound: offset as i32,
+                            placement_index,
+                        }),
+                    }
+                }
+                (key, candidates)
+            })
+            .collect();
+        Self::from_gaps(table)
+    }
+
+    /// Builds a table holding the decisions `strategy` makes for every gap shape.
+    ///
+    /// Each decision is asked on a board holding only the gap (in the leftmost slots)
+    /// and its upper neighbour, with the engine's forced-move shortcuts applied.
+    /// Randomized strategies get a generator seeded for each decision, so exporting
+    /// one twice gives the same table.
+    pub fn from_strategy(strategy: &dyn Strategy, max_slots: usize, max_values: usize) -> Self {
+        Self::from_policy(max_slots, max_values, |key, offset| {
+            let decision =
+                (key.num_slots * (max_values + 1) + key.num_values) * max_values + offset;
+            let mut rng = StdRng::seed_from_u64(decision as u64);
+            let gap = Gap {
+                lower: LOWER_BOUND,
+                upper: LOWER_BOUND + key.num_values as i32 + 1,
+                first_index: 0,
+                last_index: key.num_slots - 1,
+            };
+            let mut board = vec![None; NUM_SLOTS];
+            if key.num_slots < NUM_SLOTS && gap.upper < UPPER_BOUND {
+                board[key.num_slots] = Some(gap.upper);
+            }
+            let number = gap.lower + 1 + offset as i32;
+            choose_placement(strategy, &gap, number, &board, &mut rng) - gap.first_index
+        })
+    }
+
+    /// What the table records about how it was built.
+    pub fn metadata(&self) -> &TableMetadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: TableMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// Checks that the table was built for the game the engine plays.
+    /// `file_path` names the table in the error.
+    pub fn check_config(&self, file_path: &str) -> Result<(), TableError> {
+        match self.metadata.config {
+            Some(config) if !self.metadata.fits(GAME_CONFIG) => Err(TableError::ConfigMismatch {
+                path: file_path.to_string(),
+                table: config,
+                engine: GAME_CONFIG,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records the probability of winning each gap shape the table covers.
+    pub fn set_win_probabilities(&mut self, win_probability: impl Fn(LookupKey) -> f64) {
+        let stride = self.max_values + 1;
+        self.win_probabilities = (0..self.starts.len() - 1)
+            .map(|index| {
+                win_probability(LookupKey {
+                    num_slots: index / stride,
+                    num_values: index % stride,
+                })
+            })
+            .collect();
+    }
+
+    /// Whether the table records win probabilities.
+    pub fn has_win_probabilities(&self) -> bool {
+        !self.win_probabilities.is_empty()
+    }
+
+    /// The recorded probability of winning a gap shape, if the table has one.
+    /// Empty gaps are always won and gaps with more slots than values never are.
+    pub fn win_probability(&self, num_slots: usize, num_values: usize) -> Option<f64> {
+        if !self.has_win_probabilities() {
+            None
+        } else if num_slots == 0 {
+            Some(1.0)
+        } else if num_slots > num_values {
+            Some(0.0)
+        } else if num_slots > self.max_slots || num_values > self.max_values {
+            None
+        } else {
+            let probability =
+                self.win_probabilities[num_slots * (self.max_values + 1) + num_values];
+            Some(probability).filter(|probability| !probability.is_nan())
+        }
+    }
+
+    /// The probability of winning a gap after placing the number at `offset` in slot
+    /// `placement` and following the table afterwards, if the table records the
+    /// win probabilities of both gaps the placement creates.
+    pub fn placement_win_probability(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+        offset: usize,
+        placement: usize,
+    ) -> Option<f64> {
+        let split = split_probability(num_slots, num_values, offset, placement);
+        if split == 0.0 {
+            return Some(0.0).filter(|_| self.has_win_probabilities());
+        }
+        let left = self.win_probability(placement, offset)?;
+        let right = self.win_probability(num_slots - 1 - placement, num_values - 1 - offset)?;
+        Some(split * left * right)
+    }
+
+    /// The range of rows for a gap shape, empty if the table does not cover it.
+    fn rows(&self, num_slots: usize, num_values: usize) -> std::ops::Range<usize> {
+        if num_slots > self.max_slots || num_values > self.max_values {
+            return 0..0;
+        }
+        let index = num_slots * (self.max_values + 1) + num_values;
+        self.starts[index] as usize..self.starts[index + 1] as usize
+    }
+
+    /// Every gap shape the table covers, in order.
+    pub fn keys(&self) -> impl Iterator<Item = LookupKey> + '_ {
+        (0..=self.max_slots)
+            .flat_map(move |num_slots| {
+                (0..=self.max_values).map(move |num_values| LookupKey {
+                    num_slots,
+                    num_values,
+                })
+            })
+            .filter(|key| !self.rows(key.num_slots, key.num_values).is_empty())
+    }
+
+    /// The rows for a gap shape, sorted by `upper_bound`.
+    pub fn candidates(
+        &self,
+        num_slots: usize,
+        num_values: usize,
+    ) -> impl Iterator<Item = Candidate> + '_ {
+        self.rows(num_slots, num_values).map(|i| Candidate {
+            upper_bound: self.upper_bounds[i],
+            placement_index: self.placements[i] as usize,
+        })
+    }
+
+    /// The placement (0-indexed within the gap) for the number at `offset`, if the
+    /// table has an entry for this gap shape.
+    pub fn placement(&self, num_slots: usize, num_values: usize, offset: i32) -> Option<usize> {
+        let rows = self.rows(num_slots, num_values);
+        if rows.is_empty() {
+            return None;
+        }
+
+        // Find the first threshold at or above the offset, falling back to the last row.
+        let upper_bounds = &self.upper_bounds[rows.clone()];
+        let index = upper_bounds
+            .partition_point(|&upper_bound| upper_bound < offset)
+            .min(upper_bounds.len() - 1);
+        Some(self.placements[rows.start + index] as usize)
+    }
+
+    /// Writes the table in the compact binary format.
+    pub fn save_binary(&self, file_path: &str) -> std::io::Result<()> {
+        let gaps: Vec<GapRows> = self
+            .keys()
+            .map(|key| GapRows {
+                num_slots: key.num_slots,
+                num_values: key.num_values,
+                rows: self
+                    .candidates(key.num_slots, key.num_values)
+                    .map(|candidate| (candidate.placement_index, candidate.upper_bound))
+                    .collect(),
+                win_probability: self.win_probability(key.num_slots, key.num_values),
+            })
+            .collect();
+        let config = self.metadata.config;
+        let header = BinaryHeader {
+            max_slots: config.map_or(0, |config| config.num_slots),
+            max_values: config.map_or(0, |config| config.num_values),
+            objective: self.metadata.objective.clone().unwrap_or_default(),
+            generator: self.metadata.generator.clone().unwrap_or_default(),
+        };
+
+        let bytes = binary_table::encode(&header, &gaps)
+            .map_err(|message| std::io::Error::new(std::io::ErrorKind::InvalidInput, message))?;
+        std::fs::write(file_path, bytes)
+    }
+
+    /// Writes the table in the text format, sorted by gap shape and threshold, after
+    /// a header block holding its metadata and checksum.
+    pub fn save(&self, file_path: &str) -> std::io::Result<()> {
+        let mut body = Vec::new();
+        for key in self.keys() {
+            if let Some(win_probability) = self.win_probability(key.num_slots, key.num_values) {
+                writeln!(
+                    body,
+                    "value {} {} {}",
+                    key.num_slots, key.num_values, win_probability
+                )?;
+            }
+            for candidate in self.candidates(key.num_slots, key.num_values) {
+                writeln!(
+                    body,
+                    "{} {} {} {}",
+                    key.num_slots, key.num_values, candidate.placement_index, candidate.upper_bound
+                )?;
+            }
+        }
+
+        let mut header = Vec::new();
+        if let Some(config) = self.metadata.config {
+            writeln!(header, "# slots {}", config.num_slots)?;
+            writeln!(header, "# values {}", config.num_values)?;
+        }
+        if let Some(objective) = &self.metadata.objective {
+            writeln!(header, "# objective {}", objective)?;
+        }
+        if let Some(generator) = &self.metadata.generator {
+            writeln!(header, "# generator {}", generator)?;
+        }
+        let checksum_start = header.len();
+        header.extend_from_slice(CHECKSUM_PLACEHOLDER);
+        let checksum = binary_table::checksum(&[&header[..], &body[..]].concat());
+        header.truncate(checksum_start);
+        writeln!(header, "# checksum {:016x}", checksum)?;
+
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(file_path)?);
+        writer.write_all(&header)?;
+        writer.write_all(&body)?;
+        writer.flush()
+    }
+
+    /// Lists every offset of every gap with up to `max_slots` slots and `max_values`
+    /// values where `self` and `other` choose different placements.
+    pub fn diff(
+        &self,
+        other: &LookupTable,
+        max_slots: usize,
+        max_values: usize,
+    ) -> Vec<TableDifference> {
+        let mut differences = Vec::new();
+        for num_slots in 1..=max_slots {
+            for num_values in num_slots..=max_values {
+                for offset in 0..num_values {
+                    let placements = [
+                        self.placement(num_slots, num_values, offset as i32),
+                        other.placement(num_slots, num_values, offset as i32),
+                    ];
+                    if placements[0] != placements[1] {
+                        differences.push(TableDifference {
+                            key: LookupKey {
+                                num_slots,
+                                num_values,
+                            },
+                            offset,
+                            placements,
+                        });
+                    }
+                }
+            }
+        }
+        differences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(
+        line: usize,
+        num_slots: usize,
+        num_values: usize,
+        placement: usize,
+        upper: i32,
+    ) -> TableRow {
+        TableRow {
+            line,
+            key: LookupKey {
+                num_slots,
+                num_values,
+            },
+            candidate: Candidate {
+                upper_bound: upper,
+                placement_index: placement,
+            },
+        }
+    }
+
+    /// The problems with `rows` other than the gap shapes they leave out.
+    fn row_problems(rows: &[TableRow]) -> Vec<TableProblem> {
+        check_rows(rows)
+            .into_iter()
+            .filter(|problem| !matches!(problem, TableProblem::MissingGap { .. }))
+            .collect()
+    }
+
+    #[test]
+    fn a_table_without_rows_misses_every_gap_shape() {
+        let problems = check_rows(&[]);
+        let shapes: usize = (1..=NUM_SLOTS)
+            .map(|num_slots| NUM_VALUES - num_slots + 1)
+            .sum();
+        assert_eq!(problems.len(), shapes);
+        assert!(problems
+            .iter()
+            .all(|problem| matches!(problem, TableProblem::MissingGap { .. })));
+    }
+
+    #[test]
+    fn well_formed_rows_have_no_problems() {
+        let rows = [row(1, 2, 5, 0, 1), row(2, 2, 5, 1, 4), row(3, 1, 3, 0, 2)];
+        assert!(row_problems(&rows).is_empty());
+        assert!(check_rows(&rows).iter().all(
+            |problem| !matches!(problem, TableProblem::MissingGap { key } if *key == rows[0].key)
+        ));
+    }
+
+    #[test]
+    fn finds_a_placement_outside_its_gap() {
+        let problems = row_problems(&[row(7, 2, 5, 2, 4)]);
+        assert!(matches!(
+            problems[..],
+            [TableProblem::PlacementOutOfRange {
+                line: 7,
+                placement_index: 2,
+                ..
+            }]
+        ));
+        assert!(problems[0].to_string().starts_with("line 7:"));
+    }
+
+    #[test]
+    fn finds_thresholds_that_do_not_increase() {
+        let problems = row_problems(&[row(1, 2, 5, 0, 2), row(2, 2, 5, 1, 2), row(3, 2, 5, 1, 4)]);
+        assert!(matches!(
+            problems[..],
+            [TableProblem::ThresholdNotIncreasing {
+                line: 2,
+                upper_bound: 2,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn finds_a_last_threshold_short_of_the_largest_offset() {
+        let problems = row_problems(&[row(1, 2, 5, 0, 1), row(2, 2, 5, 1, 3)]);
+        assert!(matches!(
+            problems[..],
+            [TableProblem::LastThreshold {
+                line: 2,
+                upper_bound: 3,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn finds_gaps_larger_than_the_game() {
+        let problems = row_problems(&[
+            row(1, NUM_SLOTS + 1, NUM_VALUES, 0, NUM_VALUES as i32 - 1),
+            row(2, 1, NUM_VALUES + 1, 0, NUM_VALUES as i32),
+        ]);
+        assert!(matches!(
+            problems[..],
+            [
+                TableProblem::GapTooLarge { line: 1, .. },
+                TableProblem::GapTooLarge { line: 2, .. }
+            ]
+        ));
+    }
+
+    #[test]
+    fn finds_win_probabilities_outside_0_and_1() {
+        let key = LookupKey {
+            num_slots: 1,
+            num_values: 1,
+        };
+        let values: Vec<TableValue> = [0.0, 1.0, 1.5, -0.1, f64::NAN]
+            .into_iter()
+            .enumerate()
+            .map(|(line, win_probability)| TableValue {
+                line,
+                key,
+                win_probability,
+            })
+            .collect();
+        let lines: Vec<usize> = check_values(&values)
+            .iter()
+            .map(|problem| match problem {
+                TableProblem::ValueOutOfRange { line, .. } => *line,
+                other => panic!("unexpected problem {}", other),
+            })
+            .collect();
+        assert_eq!(lines, [2, 3, 4]);
+    }
+
+    #[test]
+    fn loading_a_table_with_a_huge_gap_is_an_error() {
+        let bytes = b"1 1 0 0\n1 100000000 0 0\n";
+        match LookupTable::from_bytes(bytes, "huge.txt") {
+            Err(TableError::Invalid { problems, .. }) => assert!(matches!(
+                problems[..],
+                [TableProblem::GapTooLarge { line: 2, .. }, ..]
+            )),
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("loaded a table with a huge gap"),
+        }
+    }
+
+    /// Saves a small table with every kind of metadata and reads the file back.
+    fn saved_text() -> Vec<u8> {
+        let mut table = LookupTable::from_policy(3, 6, |key, offset| offset % key.num_slots);
+        table.metadata.objective = Some("wins".to_string());
+        table.metadata.generator = Some("test".to_string());
+        let path = std::env::temp_dir().join(format!("table-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        table.save(path).unwrap();
+        let bytes = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        bytes
+    }
+
+    fn is_checksum_mismatch(bytes: &[u8]) -> bool {
+        matches!(
+            LookupTable::from_bytes(bytes, "edited.txt"),
+            Err(TableError::Parse { message, .. }) if message.contains("does not match")
+        )
+    }
+
+    #[test]
+    fn loads_what_it_saves() {
+        let bytes = saved_text();
+        let table = LookupTable::from_bytes(&bytes, "saved.txt").unwrap();
+        assert_eq!(table.metadata().objective.as_deref(), Some("wins"));
+        assert_eq!(table.metadata().generator.as_deref(), Some("test"));
+        assert_eq!(table.placement(2, 5, 3), Some(1));
+    }
+
+    #[test]
+    fn the_checksum_covers_the_header() {
+        let text = String::from_utf8(saved_text()).unwrap();
+        for (from, to) in [
+            ("# slots 3", "# slots 4"),
+            ("# values 6", "# values 7"),
+            ("# objective wins", "# objective ends"),
+            ("# generator test", "# generator best"),
+        ] {
+            assert!(text.contains(from));
+            assert!(is_checksum_mismatch(text.replacen(from, to, 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn the_checksum_covers_the_rows() {
+        let mut bytes = saved_text();
+        let last_digit = bytes.iter().rposition(u8::is_ascii_digit).unwrap();
+        bytes[last_digit] = if bytes[last_digit] == b'0' {
+            b'1'
+        } else {
+            b'0'
+        };
+        assert!(is_checksum_mismatch(&bytes));
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+use std::io::{self, BufRead};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::engine::{
+    choose_placement, find_valid_gap, game_seed, LOWER_BOUND, NUM_SLOTS, UPPER_BOUND,
+};
+use crate::strategy::Strategy;
+
+/// A single draw in a recorded game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceMove {
+    pub number: i32,
+    /// The slot the number was placed in, or `None` if it was never placed
+    /// (the draw that lost the game, or a draw recorded after it).
+    pub slot: Option<usize>,
+}
+
+/// The draws and placements of one game, in the order they happened.
+///
+/// In the text format each game is one line of space separated moves. A placed
+/// number is written `number:slot` and a number that was not placed is written
+/// on its own, e.g. `512:10 37:0 40:1 38`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameTrace {
+    pub moves: Vec<TraceMove>,
+}
+
+impl GameTrace {
+    /// Plays `strategy` on `numbers` and records every placement it makes.
+    pub fn record(strategy: &dyn Strategy, numbers: &[i32]) -> Self {
+        let mut board = vec![None; NUM_SLOTS];
+        let mut lost = false;
+        let mut moves = Vec::with_capacity(numbers.len());
+        // The same generator the engine gives the strategy for these draws.
+        let mut rng = StdRng::seed_from_u64(game_seed(numbers));
+        for &number in numbers {
+            let slot = if lost {
+                None
+            } else if let Some(gap) = find_valid_gap(&board, number) {
+                let slot = choose_placement(strategy, &gap, number, &board, &mut rng);
+                board[slot] = Some(number);
+                Some(slot)
+            } else {
+                lost = true;
+                None
+            };
+            moves.push(TraceMove { number, slot });
+        }
+        Self { moves }
+    }
+
+    /// The drawn numbers in order.
+    pub fn numbers(&self) -> Vec<i32> {
+        self.moves.iter().map(|m| m.number).collect()
+    }
+
+    /// The number of moves that placed a number.
+    pub fn placed_count(&self) -> usize {
+        self.moves.iter().filter(|m| m.slot.is_some()).count()
+    }
+
+    /// Whether a drawn number could not be placed.
+    pub fn lost(&self) -> bool {
+        self.moves.iter().any(|m| m.slot.is_none())
+    }
+
+    /// Parses one line of the text format and checks that every placement was legal.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut board = vec![None; NUM_SLOTS];
+        let mut moves = Vec::new();
+        for token in line.split_whitespace() {
+            let (number, slot) = match token.split_once(':') {
+                Some((number, slot)) => (number, Some(slot)),
+                None => (token, None),
+            };
+            let number: i32 = number
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", number))?;
+            if number <= LOWER_BOUND || number >= UPPER_BOUND {
+                return Err(format!(
+                    "{} is outside the range {}..{}",
+                    number,
+                    LOWER_BOUND + 1,
+                    UPPER_BOUND
+                ));
+            }
+            if moves.iter().any(|m: &TraceMove| m.number == number) {
+                return Err(format!("{} is drawn more than once", number));
+            }
+
+            let slot = match slot {
+                Some(slot) => {
+                    let slot: usize = slot
+                        .parse()
+                        .map_err(|_| format!("'{}' is not a slot index", slot))?;
+                    if moves.iter().any(|m: &TraceMove| m.slot.is_none()) {
+                        return Err(format!("{} is placed after the game was lost", number));
+                    }
+                    let gap = find_valid_gap(&board, number)
+                        .ok_or_else(|| format!("{} cannot be placed on the board", number))?;
+                    if slot < gap.first_index || slot > gap.last_index {
+                        return Err(format!(
+                            "{} placed in slot {} but must go in slots {}..={}",
+                            number, slot, gap.first_index, gap.last_index
+                        ));
+                    }
+                    board[slot] = Some(number);
+                    Some(slot)
+                }
+                None => None,
+            };
+            moves.push(TraceMove { number, slot });
+        }
+
+        if moves.len() > NUM_SLOTS {
+            return Err(format!(
+                "{} numbers drawn but the board only has {} slots",
+                moves.len(),
+                NUM_SLOTS
+            ));
+        }
+        Ok(Self { moves })
+    }
+}
+
+impl fmt::Display for GameTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, m) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match m.slot {
+                Some(slot) => write!(f, "{}:{}", m.number, slot)?,
+                None => write!(f, "{}", m.number)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads game traces from a text file with one game per line.
+/// Blank lines and lines starting with `#` are ignored.
+pub fn load_traces(file_path: &str) -> io::Result<Vec<GameTrace>> {
+    let file = std::fs::File::open(file_path)?;
+    let reader = io::BufReader::new(file);
+
+    let mut traces = Vec::new();
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let trace = GameTrace::parse(line).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", file_path, line_index + 1, message),
+            )
+        })?;
+        traces.push(trace);
+    }
+    Ok(traces)
+}
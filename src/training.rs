@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::engine::{draw_numbers, feasible_placements, find_valid_gap, game_seed, Gap, NUM_SLOTS};
+use crate::objective::Policy;
+
+/// The tries of each placement before its estimate is trusted.
+const MIN_TRIES: u32 = 5;
+
+/// How often each placement of a gap state was tried and how often the gap was
+/// then filled completely.
+#[derive(Debug, Clone, Copy, Default)]
+struct ActionStats {
+    tries: u32,
+    fills: u32,
+}
+
+impl ActionStats {
+    fn mean(&self) -> f64 {
+        if self.tries == 0 {
+            0.0
+        } else {
+            self.fills as f64 / self.tries as f64
+        }
+    }
+
+    /// Whether this placement fills its gap significantly more often than `other`.
+    /// With so many states compared, that takes a fair number of tries of both and
+    /// three standard errors of the difference.
+    fn beats(&self, other: &ActionStats) -> bool {
+        if self.tries < MIN_TRIES || other.tries < MIN_TRIES {
+            return false;
+        }
+        let pooled = (self.fills + other.fills) as f64 / (self.tries + other.tries) as f64;
+        let variance =
+            pooled * (1.0 - pooled) * (1.0 / self.tries as f64 + 1.0 / other.tries as f64);
+        self.mean() - other.mean() > 3.0 * variance.sqrt()
+    }
+}
+
+/// Learns a gap-local placement policy by Monte Carlo control on games played
+/// in the engine.
+///
+/// A state is a gap shape and the offset of the number in it, and an action is a
+/// placement. Wins are far too rare to learn from, so the return of a decision is
+/// whether its gap ended up completely filled: the game is won exactly when every gap
+/// is, and the other gaps only affect a gap through when the game ends, which no
+/// placement inside it changes, so the placements that fill their gaps most often
+/// are the ones the solver would choose. Moves are epsilon-greedy, and a placement
+/// only replaces the base policy's once it fills its gap significantly more often,
+/// so rarely seen states keep playing like the base.
+pub struct Trainer {
+    base: Policy,
+    epsilon: f64,
+    /// The statistics of every placement of each `(num_slots, num_values, offset)` tried so far.
+    stats: HashMap<(u8, u16, u16), Vec<ActionStats>>,
+}
+
+impl Trainer {
+    pub fn new(base: Policy, epsilon: f64) -> Self {
+        Self {
+            base,
+            epsilon,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// How many gap states have been tried.
+    pub fn num_states(&self) -> usize {
+        self.stats.len()
+    }
+
+    /// The learned placement (0-indexed within the gap) for the number at `offset`.
+    pub fn greedy(&self, num_slots: usize, num_values: usize, offset: usize) -> usize {
+        let base = self.base.placement(num_slots, num_values, offset);
+        let Some(stats) = self
+            .stats
+            .get(&(num_slots as u8, num_values as u16, offset as u16))
+        else {
+            return base;
+        };
+        let mut best = (base, stats[base].mean());
+        for placement in feasible_placements(num_slots, num_values, offset) {
+            if stats[placement].mean() > best.1 && stats[placement].beats(&stats[base]) {
+                best = (placement, stats[placement].mean());
+            }
+        }
+        best.0
+    }
+
+    /// Plays one game with exploration and learns from the gaps it filled.
+    pub fn play_game(&mut self) {
+        let numbers = draw_numbers();
+        // Explore from the draws, so a seeded run learns the same table again.
+        let mut rng = StdRng::seed_from_u64(game_seed(&numbers));
+        let mut board = vec![None; NUM_SLOTS];
+        let mut decisions: Vec<((u8, u16, u16), usize, Gap)> = Vec::new();
+        for number in numbers {
+            let Some(gap) = find_valid_gap(&board, number) else {
+                break;
+            };
+            let num_slots = gap.last_index - gap.first_index + 1;
+            let num_values = (gap.upper - gap.lower - 1) as usize;
+            let offset = (number - gap.lower - 1) as usize;
+
+            let placements = feasible_placements(num_slots, num_values, offset);
+            let placement = if num_slots == 1 {
+                0
+            } else if !placements.is_empty() && rng.random_bool(self.epsilon) {
+                rng.random_range(placements)
+            } else {
+                self.greedy(num_slots, num_values, offset)
+            };
+            board[gap.first_index + placement] = Some(number);
+            if num_slots > 1 {
+                let key = (num_slots as u8, num_values as u16, offset as u16);
+                decisions.push((key, placement, gap));
+            }
+        }
+
+        for (key, placement, gap) in decisions {
+            let filled = board[gap.first_index..=gap.last_index]
+                .iter()
+                .all(Option::is_some);
+            let stats = self
+                .stats
+                .entry(key)
+                .or_insert_with(|| vec![ActionStats::default(); key.0 as usize]);
+            stats[placement].tries += 1;
+            stats[placement].fills += filled as u32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trainer whose base policy takes the lowest feasible slot, with `stats` for the
+    /// number at offset 4 of a gap of 3 slots and 10 values.
+    fn trainer(stats: [(u32, u32); 3]) -> Trainer {
+        let base = Policy::from_fn(3, 10, |num_slots, num_values, offset| {
+            *feasible_placements(num_slots, num_values, offset).start()
+        });
+        let mut trainer = Trainer::new(base, 0.1);
+        trainer.stats.insert(
+            (3, 10, 4),
+            stats
+                .iter()
+                .map(|&(tries, fills)| ActionStats { tries, fills })
+                .collect(),
+        );
+        trainer
+    }
+
+    #[test]
+    fn plays_the_base_policy_in_unseen_states() {
+        let trainer = trainer([(0, 0); 3]);
+        assert_eq!(trainer.greedy(3, 10, 4), 0);
+        assert_eq!(trainer.greedy(3, 10, 9), 2);
+        assert_eq!(trainer.greedy(2, 10, 4), 0);
+    }
+
+    #[test]
+    fn keeps_the_base_placement_until_another_beats_it() {
+        // Too few tries of the other placement to trust it.
+        assert_eq!(trainer([(100, 10), (4, 4), (0, 0)]).greedy(3, 10, 4), 0);
+        // Better, but not significantly.
+        assert_eq!(trainer([(100, 10), (100, 15), (0, 0)]).greedy(3, 10, 4), 0);
+        // Significantly better.
+        assert_eq!(trainer([(100, 10), (100, 40), (0, 0)]).greedy(3, 10, 4), 1);
+        // The best of the placements that beat the base.
+        assert_eq!(
+            trainer([(100, 10), (100, 40), (100, 60)]).greedy(3, 10, 4),
+            2
+        );
+    }
+}
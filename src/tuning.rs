@@ -0,0 +1,253 @@
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::engine::{draw_more_numbers, run_sequences_multi, NUM_SLOTS};
+use crate::objective::Objective;
+use crate::strategy::Strategy;
+
+/// A numeric parameter of a strategy and the range to search it in.
+pub struct Parameter {
+    pub name: &'static str,
+    pub low: f64,
+    pub high: f64,
+    /// Whether only whole numbers are meaningful.
+    pub integer: bool,
+}
+
+/// Builds a strategy from the values of its parameters, in order.
+pub type BuildStrategy = Box<dyn Fn(&[f64]) -> Arc<dyn Strategy>>;
+
+/// A family of strategies the tuner can build for any setting of its parameters.
+pub struct Tunable {
+    pub name: &'static str,
+    pub parameters: Vec<Parameter>,
+    pub build: BuildStrategy,
+}
+
+/// The scores of one candidate setting over the games it played.
+pub struct Candidate {
+    pub values: Vec<f64>,
+    pub games: usize,
+    total: f64,
+    total_squares: f64,
+}
+
+impl Candidate {
+    /// The mean score per game.
+    pub fn mean(&self) -> f64 {
+        self.total / self.games as f64
+    }
+
+    /// The half-width of the 95% confidence interval of the mean.
+    pub fn margin(&self) -> f64 {
+        margin(self.total, self.total_squares, self.games)
+    }
+}
+
+/// The outcome of a tuning run: every candidate, best first, and the 95% confidence
+/// interval of how much better the best scored than the runner-up on the same games.
+pub struct Tuning {
+    pub candidates: Vec<Candidate>,
+    pub difference: Option<(f64, f64)>,
+}
+
+/// Tunes `tunable` by random search with successive halving.
+///
+/// `num_candidates` settings are drawn at random from the parameter ranges and play
+/// `games` games each. The better half by `objective` survive, play twice as many
+/// new games, and so on until the last two have played. Every candidate still in
+/// the running plays the same draw sequences, so luck of the draw cancels out of
+/// their comparison, and the sequences come from `seed` so a run can be repeated.
+pub fn tune(
+    tunable: &Tunable,
+    num_candidates: usize,
+    games: usize,
+    objective: Objective,
+    seed: u64,
+    mut on_round: impl FnMut(usize, &[Candidate], &[usize]),
+) -> Tuning {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut candidates: Vec<Candidate> = (0..num_candidates)
+        .map(|_| Candidate {
+            values: tunable
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    let value = rng.random_range(parameter.low..=parameter.high);
+                    if parameter.integer {
+                        value.round()
+                    } else {
+                        value
+                    }
+                })
+                .collect(),
+            games: 0,
+            total: 0.0,
+            total_squares: 0.0,
+        })
+        .collect();
+    let strategies: Vec<(String, Arc<dyn Strategy>)> = candidates
+        .iter()
+        .map(|candidate| (tunable.name.to_string(), (tunable.build)(&candidate.values)))
+        .collect();
+
+    // Sums of the score differences between every pair of candidates on shared games.
+    let mut pair_totals = vec![(0.0, 0.0, 0); num_candidates * num_candidates];
+    let mut alive: Vec<usize> = (0..num_candidates).collect();
+    let mut round_games = games;
+    let mut round = 1;
+    loop {
+        let alive_strategies: Vec<(String, Arc<dyn Strategy>)> = alive
+            .iter()
+            .map(|&index| strategies[index].clone())
+            .collect();
+
+        // Play in batches so the draw sequences never all have to be held at once.
+        let mut played = 0;
+        while played < round_games {
+            let batch = (round_games - played).min(100_000);
+            let sequences: Vec<Vec<i32>> = (0..batch)
+                .map(|_| draw_more_numbers(&[], NUM_SLOTS, &mut rng))
+                .collect();
+            for results in run_sequences_multi(&alive_strategies, &sequences) {
+                let scores: Vec<f64> = results
+                    .iter()
+                    .map(|(_, result)| objective.score(result.placed_count, NUM_SLOTS))
+                    .collect();
+                for (i, &index) in alive.iter().enumerate() {
+                    let candidate = &mut candidates[index];
+                    candidate.games += 1;
+                    candidate.total += scores[i];
+                    candidate.total_squares += scores[i] * scores[i];
+                    for (j, &other) in alive.iter().enumerate() {
+                        let difference = scores[i] - scores[j];
+                        let pair = &mut pair_totals[index * num_candidates + other];
+                        pair.0 += difference;
+                        pair.1 += difference * difference;
+                        pair.2 += 1;
+                    }
+                }
+            }
+            played += batch;
+        }
+
+        alive.sort_by(|&a, &b| candidates[b].mean().total_cmp(&candidates[a].mean()));
+        on_round(round, &candidates, &alive);
+        alive.truncate(alive.len().div_ceil(2));
+        if alive.len() <= 1 {
+            break;
+        }
+        round_games *= 2;
+        round += 1;
+    }
+
+    // Rank by how far each candidate got, then by its score.
+    let mut order: Vec<usize> = (0..num_candidates).collect();
+    order.sort_by(|&a, &b| {
+        candidates[b]
+            .games
+            .cmp(&candidates[a].games)
+            .then(candidates[b].mean().total_cmp(&candidates[a].mean()))
+    });
+    let difference = (order.len() >= 2).then(|| {
+        let (total, total_squares, games) = pair_totals[order[0] * num_candidates + order[1]];
+        (total / games as f64, margin(total, total_squares, games))
+    });
+
+    let mut slots: Vec<Option<Candidate>> = candidates.into_iter().map(Some).collect();
+    Tuning {
+        candidates: order
+            .into_iter()
+            .map(|index| slots[index].take().unwrap())
+            .collect(),
+        difference,
+    }
+}
+
+/// The half-width of the 95% confidence interval of a mean, from the sum and the sum
+/// of squares of `count` samples.
+fn margin(total: f64, total_squares: f64, count: usize) -> f64 {
+    let count = count as f64;
+    let mean = total / count;
+    let variance = (total_squares / count - mean * mean).max(0.0) * count / (count - 1.0).max(1.0);
+    1.96 * (variance / count).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::{FirstAvailableStrategy, MiddleStrategy, OptimalWinStrategy};
+
+    /// Picks one of three fixed strategies by its only parameter.
+    fn tunable() -> Tunable {
+        Tunable {
+            name: "Pick",
+            parameters: vec![Parameter {
+                name: "strategy",
+                low: 0.0,
+                high: 2.0,
+                integer: true,
+            }],
+            build: Box::new(|values| -> Arc<dyn Strategy> {
+                match values[0] as usize {
+                    0 => Arc::new(FirstAvailableStrategy),
+                    1 => Arc::new(MiddleStrategy),
+                    _ => Arc::new(OptimalWinStrategy),
+                }
+            }),
+        }
+    }
+
+    #[test]
+    fn margin_is_the_half_width_of_the_confidence_interval() {
+        // Scores 1, 0, 1, 1, 0: mean 0.6 and sample variance 0.3.
+        let candidate = Candidate {
+            values: Vec::new(),
+            games: 5,
+            total: 3.0,
+            total_squares: 3.0,
+        };
+        assert!((candidate.mean() - 0.6).abs() < 1e-12);
+        assert!((candidate.margin() - 1.96 * (0.3_f64 / 5.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn three_candidates_play_two_rounds() {
+        let mut rounds = Vec::new();
+        let tuning = tune(
+            &tunable(),
+            3,
+            50,
+            Objective::ExpectedPlacements,
+            0,
+            |round, _, alive| rounds.push((round, alive.len())),
+        );
+        assert_eq!(rounds, [(1, 3), (2, 2)]);
+        let games: Vec<usize> = tuning.candidates.iter().map(|c| c.games).collect();
+        assert_eq!(games, [150, 150, 50]);
+    }
+
+    #[test]
+    fn difference_pairs_the_two_finalists() {
+        let tuning = tune(
+            &tunable(),
+            5,
+            50,
+            Objective::ExpectedPlacements,
+            1,
+            |_, _, _| {},
+        );
+        let [best, runner_up, ..] = &tuning.candidates[..] else {
+            panic!("fewer than two candidates");
+        };
+        // The finalists played the same games, so the mean of their differences is the
+        // difference of their means.
+        assert_eq!(best.games, runner_up.games);
+        assert_ne!(best.values, runner_up.values);
+        let (difference, margin) = tuning.difference.unwrap();
+        assert!((difference - (best.mean() - runner_up.mean())).abs() < 1e-9);
+        assert!(margin >= 0.0);
+    }
+}